@@ -1,18 +1,35 @@
 use std::{env, path::Path};
 
-fn main() {
+fn main() -> Result<(), bevy_embasset::BuildError> {
     // Do this to include all files in the asset folder:
-    bevy_embasset::include_all_assets(
+    // bevy_embasset::include_all_assets(
+    //     &Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets"),
+    // )?;
+
+    // OR this, to also generate an enum identifying all the included assets:
+    bevy_embasset::include_all_assets_with(
         &Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets"),
-    );
+        &bevy_embasset::IncludeOptions::new().generate_enum("GameAssets"),
+    )?;
+
+    // Loading the assets from disk in debug builds, and only embedding them in release builds,
+    // is done by adding `.load_from_disk(bevy_embasset::LoadFromDisk::DebugAssertions)` to the
+    // options.
 
-    // OR this, just to make sure all your assets are accounted for:
-    // if bevy_embasset::include_assets(
+    // OR use the builder, for naming the generated function, or including several folders:
+    // bevy_embasset::EmbassetBuilder::new()
+    //     .function_name("add_game_assets")
+    //     .add_folder(Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets"))
+    //     .generate()?;
+
+    // OR this, just to make sure all your assets are accounted for - warning about any that
+    // are missing, instead of failing the build:
+    // if let Err(err) = bevy_embasset::include_assets(
     //     &Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets"),
     //     &[".keepme"],
-    // )
-    // .is_err()
-    // {
-    //     std::process::exit(-1);
+    // ) {
+    //     println!("cargo:warning={}", err);
     // }
+
+    Ok(())
 }
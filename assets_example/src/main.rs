@@ -5,7 +5,26 @@ fn main() {
     let mut app = App::new();
 
     // add embasset as a plugin, include assets as found by the build script
-    app.add_embasset_plugin(add_embasset_assets).run();
+    app.add_embasset_plugin(|io| {
+        add_embasset_assets(io);
+
+        // the generated enum can be used through its AssetIo
+        io.add_handler(GameAssetsIo::new().into());
+    })
+    .run();
 }
 
 include!(concat!(env!("OUT_DIR"), "/add_embasset_assets.rs"));
+
+#[cfg(test)]
+mod tests {
+    use bevy_embasset::EnumCount;
+
+    use super::*;
+
+    #[test]
+    fn generated_enum() {
+        assert_eq!(1, GameAssets::COUNT);
+        assert_eq!(".keepme", GameAssets::Keepme.relative_path());
+    }
+}
@@ -0,0 +1,93 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use crate::normalize::{child_path, normalize, normalize_path};
+
+/// Index of the files in an archive, by normalized path.
+///
+/// Archives don't necessarily hold entries for directories, so the directories are derived from
+/// the paths of the files, with the root of the archive always being a directory.
+#[derive(Debug)]
+pub(crate) struct ArchiveIndex<T> {
+    files: BTreeMap<String, T>,
+    directories: BTreeSet<String>,
+}
+
+impl<T> ArchiveIndex<T> {
+    pub(crate) fn new() -> Self {
+        ArchiveIndex {
+            files: BTreeMap::new(),
+            directories: [String::new()].into_iter().collect(),
+        }
+    }
+
+    /// Add the file at `path` in the archive, with `entry` locating its data.
+    ///
+    /// Paths leading outside of the archive are ignored.
+    pub(crate) fn insert_file(&mut self, path: &str, entry: T) {
+        if let Some(path) = Self::enclosed(path) {
+            self.insert_parents(&path);
+            self.files.insert(path, entry);
+        }
+    }
+
+    /// Add the directory at `path` in the archive, which may be empty.
+    ///
+    /// Paths leading outside of the archive are ignored.
+    pub(crate) fn insert_directory(&mut self, path: &str) {
+        if let Some(path) = Self::enclosed(path) {
+            self.insert_parents(&path);
+            self.directories.insert(path);
+        }
+    }
+
+    /// Number of files in the archive.
+    pub(crate) fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// The entry of the file at `path`, if in the archive.
+    pub(crate) fn get(&self, path: &Path) -> Option<&T> {
+        self.files.get(&normalize(path))
+    }
+
+    /// Checks if `path` is a directory in the archive.
+    pub(crate) fn is_directory(&self, path: &Path) -> bool {
+        self.directories.contains(&normalize(path))
+    }
+
+    /// Paths of the files and directories directly in the directory `path`, in sorted order.
+    ///
+    /// Returns `None` if the directory isn't in the archive.
+    pub(crate) fn read_directory(&self, path: &Path) -> Option<Vec<PathBuf>> {
+        let path = normalize(path);
+        if !self.directories.contains(&path) {
+            return None;
+        }
+        let is_child = |child: &&String| child_path(&path, child) == Some(child.as_str());
+        let mut paths = self
+            .files
+            .keys()
+            .filter(is_child)
+            .chain(self.directories.iter().filter(is_child))
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        paths.sort();
+        Some(paths)
+    }
+
+    fn enclosed(path: &str) -> Option<String> {
+        let path = normalize_path(path);
+        (!path.is_empty() && path != ".." && !path.starts_with("../")).then_some(path)
+    }
+
+    fn insert_parents(&mut self, path: &str) {
+        let mut path = path;
+        while let Some((parent, _)) = path.rsplit_once('/') {
+            self.directories.insert(parent.to_string());
+            path = parent;
+        }
+    }
+}
@@ -1,10 +1,445 @@
 use std::{
-    env,
-    fs::{self, File},
-    io::Write,
+    collections::HashMap,
+    env, fs,
     path::{Path, PathBuf},
 };
 
+use crate::{normalize::normalize_path, Compression};
+
+mod builder;
+mod error;
+mod filter;
+mod macros;
+mod manifest;
+mod names;
+mod portability;
+mod report;
+mod transform;
+mod usage;
+mod validate;
+pub use builder::EmbassetBuilder;
+pub use error::{BuildError, DuplicatePath, InvalidAsset, MissingAsset};
+use filter::AssetFilter;
+pub use filter::IGNORE_FILE_NAME;
+pub use macros::check_asset_macros;
+pub use portability::{Portability, PortabilityIssue};
+pub use report::BudgetViolation;
+use transform::Transform;
+pub use transform::{TransformError, Transformed};
+use validate::Validator;
+
+/// Options controlling how assets are included by [`include_all_assets_with`] and
+/// [`include_assets_with`].
+///
+/// # Example
+///
+/// ```ignore
+/// let options = IncludeOptions::new()
+///     .compress(Compression::Zstd)
+///     .compress_extension("png", Compression::None)
+///     .compress_path("music/theme.wav", Compression::Deflate)
+///     .exclude("**/*.psd");
+/// ```
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, Default)]
+pub struct IncludeOptions {
+    compression: Compression,
+    extension_compression: HashMap<String, Compression>,
+    path_compression: HashMap<String, Compression>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    asset_enum: Option<String>,
+    load_from_disk: Option<LoadFromDisk>,
+    layout: AssetLayout,
+    transforms: HashMap<String, Transform>,
+    validate_formats: bool,
+    validators: HashMap<String, Validator>,
+    max_total_size: Option<u64>,
+    max_asset_size: Option<u64>,
+    fail_over_budget: bool,
+    symlinks: Symlinks,
+    non_utf8_names: NonUtf8Names,
+    portability: Portability,
+    max_path_length: Option<usize>,
+}
+
+/// How the generated code embeds the assets.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum AssetLayout {
+    /// One `include_bytes!` per asset, added to a `HashMap` at startup.
+    #[default]
+    Individual,
+    /// A single [`AssetPack`](crate::AssetPack).
+    Pack,
+    /// An [`AssetTable`](crate::AssetTable).
+    Table,
+}
+
+/// Build configuration in which the generated code loads assets from disk, instead of embedding
+/// them.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadFromDisk {
+    /// Load from disk when `debug_assertions` are enabled, i.e. in debug builds.
+    DebugAssertions,
+    /// Load from disk when the cargo feature with the given name is enabled, for the crate
+    /// including the generated code.
+    Feature(String),
+}
+
+impl LoadFromDisk {
+    /// The cfg predicate enabling this mode in the generated code.
+    fn cfg(&self) -> String {
+        match self {
+            LoadFromDisk::DebugAssertions => "debug_assertions".to_string(),
+            LoadFromDisk::Feature(feature) => format!("feature = {:?}", feature),
+        }
+    }
+
+    /// The environment variable Cargo sets for the build script, when this mode is enabled.
+    fn env_var(&self) -> String {
+        match self {
+            LoadFromDisk::DebugAssertions => "CARGO_CFG_DEBUG_ASSERTIONS".to_string(),
+            LoadFromDisk::Feature(feature) => {
+                format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"))
+            }
+        }
+    }
+
+    /// Checks if the build script is building for a configuration loading assets from disk.
+    fn is_enabled(&self) -> bool {
+        env::var_os(self.env_var()).is_some()
+    }
+}
+
+/// How symlinks are treated when traversing an asset folder.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Symlinks {
+    /// Embed the files and directories symlinks point to. A symlink leading back to a directory
+    /// being traversed fails the build with [`BuildError::SymlinkCycle`].
+    #[default]
+    Follow,
+    /// Leave out symlinks, along with everything they point to.
+    Skip,
+}
+
+/// How files and directories with names that aren't valid UTF-8 are treated when traversing an
+/// asset folder.
+///
+/// Such names can't be requested through Bevy, so they can never be loaded if embedded.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonUtf8Names {
+    /// Fail the build with [`BuildError::NonUtf8Path`].
+    #[default]
+    Reject,
+    /// Leave them out, emitting a warning naming each of them.
+    Skip,
+}
+
+impl IncludeOptions {
+    /// Creates a new set of options, that will include all assets uncompressed.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compress all assets using `compression`, unless otherwise specified by extension or path.
+    ///
+    /// Assets that don't shrink by compressing them are always included uncompressed.
+    #[must_use]
+    pub fn compress(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Compress all assets with the extension `extension` (case insensitive) using `compression`.
+    #[must_use]
+    pub fn compress_extension(mut self, extension: &str, compression: Compression) -> Self {
+        self.extension_compression
+            .insert(extension.to_lowercase(), compression);
+        self
+    }
+
+    /// Compress the asset embedded as `path` using `compression`.
+    ///
+    /// The path is relative to the asset folder, including any mount prefix.
+    #[must_use]
+    pub fn compress_path(mut self, path: &str, compression: Compression) -> Self {
+        self.path_compression
+            .insert(normalize_path(path), compression);
+        self
+    }
+
+    /// Only include assets matching the glob `pattern`.
+    ///
+    /// Patterns are matched against the path relative to the asset folder, using `/` as separator.
+    /// A `*` also matches `/`, meaning e.g. `*.png` matches PNG files in all subfolders.
+    ///
+    /// Can be called multiple times, in which case an asset needs to match just one of the patterns.
+    /// Only used by [`include_all_assets_with`].
+    #[must_use]
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(pattern.to_string());
+        self
+    }
+
+    /// Exclude assets matching the glob `pattern`.
+    ///
+    /// Patterns are matched the same way as for [`include`](IncludeOptions::include), and exclusion
+    /// takes precedence over inclusion. A pattern ending in `/**`, e.g. `sources/**`, excludes the
+    /// matching directories as a whole, so they aren't even traversed. Only used by
+    /// [`include_all_assets_with`].
+    #[must_use]
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(pattern.to_string());
+        self
+    }
+
+    /// Set how symlinks in the asset folder are treated. Defaults to [`Symlinks::Follow`].
+    ///
+    /// Only used by [`include_all_assets_with`], as listed assets are always followed.
+    #[must_use]
+    pub fn symlinks(mut self, symlinks: Symlinks) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Set how names in the asset folder that aren't valid UTF-8 are treated. Defaults to
+    /// [`NonUtf8Names::Reject`].
+    ///
+    /// Only used by [`include_all_assets_with`].
+    #[must_use]
+    pub fn non_utf8_names(mut self, policy: NonUtf8Names) -> Self {
+        self.non_utf8_names = policy;
+        self
+    }
+
+    /// Set what to do about asset paths that won't work on all platforms. Defaults to
+    /// [`Portability::Warn`].
+    ///
+    /// Reports assets differing only in case, Windows reserved names (e.g. `CON` or `aux.png`),
+    /// characters not allowed on Windows, names ending with a dot or space, and paths longer than
+    /// [`max_path_length`](IncludeOptions::max_path_length).
+    #[must_use]
+    pub fn portability(mut self, portability: Portability) -> Self {
+        self.portability = portability;
+        self
+    }
+
+    /// Set the length limit used by the [`portability`](IncludeOptions::portability) check.
+    /// Defaults to 200 characters.
+    #[must_use]
+    pub fn max_path_length(mut self, length: usize) -> Self {
+        self.max_path_length = Some(length);
+        self
+    }
+
+    /// Additionally generate an enum identifying the included assets, and a struct implementing
+    /// [`AssetIo`](bevy::asset::AssetIo) serving them - just like the [`assets!`](crate::assets)
+    /// macro does.
+    ///
+    /// The enum is named `name`, and the struct `<name>Io`, both with the same visibility as the
+    /// generated function (see [`EmbassetBuilder::visibility`]). The variant names are derived from
+    /// the asset paths, e.g. `textures/ship_red.png` becomes `TexturesShipRed`. If several assets
+    /// result in the same variant name, the build fails.
+    ///
+    /// As with the macro, the crate including the generated code must depend on `paste` and
+    /// `strum`.
+    #[must_use]
+    pub fn generate_enum(mut self, name: &str) -> Self {
+        self.asset_enum = Some(name.to_string());
+        self
+    }
+
+    /// Make the generated code load the assets from their source files at runtime, instead of
+    /// embedding them, when built in the configuration given by `when`.
+    ///
+    /// In that configuration, changing an asset neither reruns the build script, nor recompiles
+    /// the crate including the generated code. Adding or removing assets, however, is only picked
+    /// up once the build script runs again. The generated function and enum are the same in both
+    /// configurations.
+    #[must_use]
+    pub fn load_from_disk(mut self, when: LoadFromDisk) -> Self {
+        self.load_from_disk = Some(when);
+        self
+    }
+
+    /// Embed all assets as a single [`AssetPack`](crate::AssetPack), instead of one
+    /// `include_bytes!` per asset.
+    ///
+    /// This speeds up compilation and startup when embedding many assets, as the generated
+    /// function adds a single pack, that is searched directly when loading assets.
+    ///
+    /// Overrides [`IncludeOptions::static_table`].
+    #[must_use]
+    pub fn pack(mut self) -> Self {
+        self.layout = AssetLayout::Pack;
+        self
+    }
+
+    /// Embed the assets in an [`AssetTable`](crate::AssetTable): a perfect-hash table generated at
+    /// compile time, along with an index of the directories.
+    ///
+    /// Adding the table doesn't allocate, and both looking up assets and reading directories is
+    /// done without scanning all of the assets.
+    ///
+    /// Overrides [`IncludeOptions::pack`].
+    #[must_use]
+    pub fn static_table(mut self) -> Self {
+        self.layout = AssetLayout::Table;
+        self
+    }
+
+    /// Transform all assets with the extension `extension` (case insensitive) using `transform`,
+    /// e.g. for minifying or converting them.
+    ///
+    /// The transform is given the path of the asset (including any mount prefix) and its
+    /// content, and returns the content to embed instead - optionally along with a new path for
+    /// the asset. The output is written to `OUT_DIR`, and cached by the path and content of the
+    /// asset, so unchanged assets aren't transformed again until the build script itself changes.
+    /// Cached output that is no longer used is removed when generating.
+    ///
+    /// When [loading from disk](IncludeOptions::load_from_disk), transformed assets are loaded
+    /// from the transformed output.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let options = IncludeOptions::new().transform("json", |_path, data| {
+    ///     let value: serde_json::Value = serde_json::from_slice(data)?;
+    ///     Ok(serde_json::to_vec(&value)?.into())
+    /// });
+    /// ```
+    #[must_use]
+    pub fn transform<F>(mut self, extension: &str, transform: F) -> Self
+    where
+        F: Fn(&str, &[u8]) -> Result<Transformed, TransformError> + Send + Sync + 'static,
+    {
+        self.transforms
+            .insert(extension.to_lowercase(), Transform::new(transform));
+        self
+    }
+
+    /// Validate assets of well-known formats before embedding them, failing the build if any are
+    /// invalid.
+    ///
+    /// PNG, JPEG, WAV and Ogg files are checked for a valid header, while JSON, RON and TOML
+    /// files must be valid UTF-8 and parse. Assets are validated after any
+    /// [transform](IncludeOptions::transform).
+    #[must_use]
+    pub fn validate(mut self) -> Self {
+        self.validate_formats = true;
+        self
+    }
+
+    /// Validate all assets with the extension `extension` (case insensitive) using `validator`,
+    /// failing the build if any are invalid.
+    ///
+    /// The validator is given the path of the asset and its content, and runs regardless of
+    /// [`IncludeOptions::validate`] - after the built-in validation, if enabled.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let options = IncludeOptions::new().validator("level", |_path, data| {
+    ///     parse_level(data)?;
+    ///     Ok(())
+    /// });
+    /// ```
+    #[must_use]
+    pub fn validator<F>(mut self, extension: &str, validator: F) -> Self
+    where
+        F: Fn(&str, &[u8]) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.validators
+            .insert(extension.to_lowercase(), Validator::new(validator));
+        self
+    }
+
+    /// Warn if the embedded assets take up more than `bytes` in total.
+    ///
+    /// The size is measured after any compression. Use [`IncludeOptions::fail_over_budget`] to
+    /// fail the build instead.
+    #[must_use]
+    pub fn max_total_size(mut self, bytes: u64) -> Self {
+        self.max_total_size = Some(bytes);
+        self
+    }
+
+    /// Warn if any embedded asset takes up more than `bytes`.
+    ///
+    /// The size is measured after any compression. Use [`IncludeOptions::fail_over_budget`] to
+    /// fail the build instead.
+    #[must_use]
+    pub fn max_asset_size(mut self, bytes: u64) -> Self {
+        self.max_asset_size = Some(bytes);
+        self
+    }
+
+    /// Fail the build with [`BuildError::OverBudget`], instead of emitting warnings, when the
+    /// size budgets are exceeded.
+    #[must_use]
+    pub fn fail_over_budget(mut self) -> Self {
+        self.fail_over_budget = true;
+        self
+    }
+
+    /// Validates the asset embedded as `asset`, returning the reason if it is invalid.
+    fn validate_asset(&self, asset: &str, data: &[u8]) -> Result<(), String> {
+        let extension = match Path::new(asset).extension() {
+            Some(extension) => extension.to_string_lossy().to_lowercase(),
+            None => return Ok(()),
+        };
+        if self.validate_formats {
+            validate::validate_format(&extension, data)?;
+        }
+        match self.validators.get(&extension) {
+            Some(validator) => validator.validate(asset, data),
+            None => Ok(()),
+        }
+    }
+
+    fn transform_for(&self, asset: &str) -> Option<&Transform> {
+        Path::new(asset)
+            .extension()
+            .and_then(|ext| self.transforms.get(&ext.to_string_lossy().to_lowercase()))
+    }
+
+    fn compression_for(&self, asset: &str) -> Compression {
+        if let Some(compression) = self.path_compression.get(asset) {
+            return *compression;
+        }
+        Path::new(asset)
+            .extension()
+            .and_then(|ext| {
+                self.extension_compression
+                    .get(&ext.to_string_lossy().to_lowercase())
+            })
+            .copied()
+            .unwrap_or(self.compression)
+    }
+}
+
 /// Generate a function for including *all* assets from a folder in [`EmbassetIo`](crate::EmbassetIo).
 ///
 /// For use from a build script (`build.rs`).
@@ -21,38 +456,31 @@ use std::{
 ///
 /// Feature: `build`
 ///
-pub fn include_all_assets(asset_folder: &Path) {
-    let method_name = "add_embasset_assets";
-    let mut output_file =
-        File::create(Path::new(&env::var_os("OUT_DIR").unwrap()).join("add_embasset_assets.rs"))
-            .unwrap();
-
-    println!("cargo:rerun-if-changed={}", asset_folder.display());
+/// # Errors
+///
+/// Fails if the asset folder could not be read, or the generated code could not be written.
+pub fn include_all_assets(asset_folder: &Path) -> Result<(), BuildError> {
+    include_all_assets_with(asset_folder, &IncludeOptions::default())
+}
 
-    output_file
-        .write_all(
-            format!(
-                "fn {}(#[allow(unused)] in_memory: &mut bevy_embasset::EmbassetIo){{\n",
-                method_name
-            )
-            .as_ref(),
-        )
-        .unwrap();
-    visit_dirs(asset_folder)
-        .iter()
-        .map(|path| (path, path.strip_prefix(asset_folder).unwrap()))
-        .for_each(|(fullpath, path)| {
-            output_file.write_all(
-                format!(
-                    "    in_memory.add_embedded_asset(std::path::Path::new({:?}), include_bytes!({:?}));",
-                    path.to_string_lossy(),
-                    fullpath.to_string_lossy()
-                )
-                .as_ref(),
-            )
-            .unwrap();
-        });
-    output_file.write_all("}".as_ref()).unwrap();
+/// Same as [`include_all_assets`], but using the specified [`IncludeOptions`].
+///
+/// Besides the include/exclude patterns of the options, files can be left out by listing them
+/// in an [`.embassetignore`](IGNORE_FILE_NAME) file (gitignore syntax) in the root of the asset
+/// folder.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+pub fn include_all_assets_with(
+    asset_folder: &Path,
+    options: &IncludeOptions,
+) -> Result<(), BuildError> {
+    EmbassetBuilder::new()
+        .options(options.clone())
+        .add_folder(asset_folder)
+        .generate()
 }
 
 /// Generate a function for including specific assets in [`EmbassetIo`](crate::EmbassetIo).
@@ -71,57 +499,178 @@ pub fn include_all_assets(asset_folder: &Path) {
 ///
 /// Feature: `build`
 ///
-pub fn include_assets(asset_folder: &Path, assets: &[&str]) -> Result<(), String> {
-    let method_name = "add_embasset_assets";
-    let mut output_file =
-        File::create(Path::new(&env::var_os("OUT_DIR").unwrap()).join("add_embasset_assets.rs"))
-            .unwrap();
+/// # Errors
+///
+/// Fails with [`BuildError::MissingAssets`], listing all of the assets that could not be found.
+pub fn include_assets(asset_folder: &Path, assets: &[&str]) -> Result<(), BuildError> {
+    include_assets_with(asset_folder, assets, &IncludeOptions::default())
+}
 
-    println!("cargo:rerun-if-changed={}", asset_folder.display());
+/// Same as [`include_assets`], but using the specified [`IncludeOptions`].
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+pub fn include_assets_with(
+    asset_folder: &Path,
+    assets: &[&str],
+    options: &IncludeOptions,
+) -> Result<(), BuildError> {
+    EmbassetBuilder::new()
+        .options(options.clone())
+        .add_assets(asset_folder, assets)
+        .generate()
+}
 
-    output_file
-        .write_all(
-            format!(
-                "fn {}(#[allow(unused)] in_memory: &mut bevy_embasset::EmbassetIo){{\n",
-                method_name
-            )
-            .as_ref(),
-        )
-        .unwrap();
-
-    for asset in assets {
-        let path = asset_folder.join(asset);
-        if !path.exists() {
-            let err = format!("Asset not found: {}", path.display());
-            println!("cargo:warning={}", err);
-            return Err(err);
+/// Collects all included files from `asset_folder`, along with the path used for embedding them.
+///
+/// If `watch` is set, emits `cargo:rerun-if-changed` for the paths given by
+/// [`Collector::rerun_paths`].
+fn collect_folder(
+    asset_folder: &Path,
+    options: &IncludeOptions,
+    watch: bool,
+) -> Result<Vec<(String, PathBuf)>, BuildError> {
+    let mut collector = Collector::new(asset_folder, options)?;
+    if asset_folder.is_dir() {
+        collector.collect(asset_folder)?;
+    }
+
+    if watch {
+        for path in collector.rerun_paths() {
+            println!("cargo:rerun-if-changed={}", path.display());
         }
-        output_file.write_all(
-            format!(
-                "    in_memory.add_embedded_asset(std::path::Path::new({:?}), include_bytes!({:?}));",
-                asset,
-                path.to_string_lossy()
-            )
-            .as_ref(),
-        )
-        .unwrap();
     }
+    collector
+        .files
+        .into_iter()
+        .map(|fullpath| {
+            let path = relative_path(asset_folder, &fullpath)?;
+            Ok((path, fullpath))
+        })
+        .collect()
+}
 
-    output_file.write_all("}".as_ref()).unwrap();
+/// The path of `fullpath` relative to `asset_folder`.
+fn relative_path(asset_folder: &Path, fullpath: &Path) -> Result<String, BuildError> {
+    let relative = fullpath.strip_prefix(asset_folder).unwrap_or(fullpath);
+    relative
+        .to_str()
+        .map(normalize_path)
+        .ok_or_else(|| BuildError::NonUtf8Path(fullpath.to_path_buf()))
+}
 
-    Ok(())
+/// Traverses an asset folder, collecting the files to include.
+struct Collector<'a> {
+    asset_folder: &'a Path,
+    filter: AssetFilter,
+    symlinks: Symlinks,
+    non_utf8_names: NonUtf8Names,
+    /// The included files.
+    files: Vec<PathBuf>,
+    /// All traversed directories.
+    dirs: Vec<PathBuf>,
+    /// Canonical paths of the directories currently being traversed, for detecting cycles.
+    ancestors: Vec<PathBuf>,
 }
 
-fn visit_dirs(dir: &Path) -> Vec<PathBuf> {
+impl<'a> Collector<'a> {
+    fn new(asset_folder: &'a Path, options: &IncludeOptions) -> Result<Self, BuildError> {
+        Ok(Collector {
+            asset_folder,
+            filter: AssetFilter::new(asset_folder, &options.include, &options.exclude)?,
+            symlinks: options.symlinks,
+            non_utf8_names: options.non_utf8_names,
+            files: vec![],
+            dirs: vec![],
+            ancestors: vec![],
+        })
+    }
+
+    /// Paths to emit `cargo:rerun-if-changed` for: the ignore file, every traversed directory (so
+    /// added and removed files are noticed) and every included file.
+    fn rerun_paths(&self) -> Vec<PathBuf> {
+        let ignore_file = self.asset_folder.join(IGNORE_FILE_NAME);
+        ignore_file
+            .is_file()
+            .then_some(ignore_file)
+            .into_iter()
+            .chain(self.dirs.iter().cloned())
+            .chain(self.files.iter().cloned())
+            .collect()
+    }
+
+    /// Collects the files to include from `dir` and its subfolders.
+    ///
+    /// Directories left out by the filter are not traversed.
+    fn collect(&mut self, dir: &Path) -> Result<(), BuildError> {
+        let canonical = fs::canonicalize(dir).map_err(BuildError::io(dir))?;
+        if self.ancestors.contains(&canonical) {
+            return Err(BuildError::SymlinkCycle {
+                link: dir.to_path_buf(),
+                target: canonical,
+            });
+        }
+        self.ancestors.push(canonical);
+        self.dirs.push(dir.to_path_buf());
+
+        let mut entries = fs::read_dir(dir)
+            .and_then(|entries| {
+                entries
+                    .map(|entry| entry.map(|entry| entry.path()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .map_err(BuildError::io(dir))?;
+        entries.sort();
+        for path in entries {
+            if path.file_name().and_then(|name| name.to_str()).is_none() {
+                match self.non_utf8_names {
+                    NonUtf8Names::Reject => return Err(BuildError::NonUtf8Path(path)),
+                    NonUtf8Names::Skip => {
+                        println!(
+                            "cargo:warning=Skipping {:?}, as the name is not valid UTF-8",
+                            path
+                        );
+                        continue;
+                    }
+                }
+            }
+            if self.symlinks == Symlinks::Skip {
+                let metadata = fs::symlink_metadata(&path).map_err(BuildError::io(&path))?;
+                if metadata.file_type().is_symlink() {
+                    continue;
+                }
+            }
+            let relative = path.strip_prefix(self.asset_folder).unwrap_or(&path);
+            if path.is_dir() {
+                if !self.filter.is_ignored_dir(relative) {
+                    self.collect(&path)?;
+                }
+            } else if self.filter.is_included_file(relative) {
+                self.files.push(path);
+            }
+        }
+        self.ancestors.pop();
+        Ok(())
+    }
+}
+
+/// All files in `dir` and its subfolders, relative to `asset_folder`.
+///
+/// Used for suggesting alternatives to missing assets, so errors are ignored. Symlinked directories
+/// are not followed, as they could form a cycle.
+fn all_files(asset_folder: &Path, dir: &Path) -> Vec<String> {
     let mut collected = vec![];
-    if dir.is_dir() {
-        for entry in fs::read_dir(dir).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_symlink() && path.is_dir() {
+                continue;
+            }
             if path.is_dir() {
-                collected.append(&mut visit_dirs(&path));
-            } else {
-                collected.push(path);
+                collected.append(&mut all_files(asset_folder, &path));
+            } else if let Ok(relative) = relative_path(asset_folder, &path) {
+                collected.push(relative);
             }
         }
     }
@@ -131,10 +680,16 @@ fn visit_dirs(dir: &Path) -> Vec<PathBuf> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::{test_dir, write_files};
 
     #[test]
     fn visit_src_dirs() {
-        let result = visit_dirs(Path::new("./assets_example"));
+        let asset_folder = Path::new("./assets_example");
+        let mut collector = Collector::new(asset_folder, &IncludeOptions::new()).unwrap();
+        collector.collect(asset_folder).unwrap();
+        let (result, dirs) = (collector.files, collector.dirs);
+        assert!(dirs.contains(&asset_folder.to_path_buf()));
+        assert!(dirs.contains(&asset_folder.join("src")));
         assert!(result
             .iter()
             .any(|p| p.display().to_string() == *"./assets_example/build.rs"));
@@ -145,4 +700,174 @@ mod test {
             .iter()
             .any(|p| p.display().to_string() == *"./assets_example/assets/.keepme"));
     }
+
+    #[test]
+    fn collect_filtered_assets() {
+        let asset_folder = Path::new("./assets_example");
+        let options = IncludeOptions::new().exclude("src/**");
+        let mut collector = Collector::new(asset_folder, &options).unwrap();
+        collector.collect(asset_folder).unwrap();
+        let (files, dirs) = (collector.files, collector.dirs);
+        assert!(files.contains(&asset_folder.join("build.rs")));
+        assert!(files.contains(&asset_folder.join("assets/.keepme")));
+        assert!(!files.contains(&asset_folder.join("src/main.rs")));
+        assert!(dirs.contains(&asset_folder.join("assets")));
+        assert!(!dirs.contains(&asset_folder.join("src")));
+    }
+
+    #[test]
+    fn excluded_files_are_not_watched() {
+        let asset_folder = test_dir("rerun-paths");
+        write_files(
+            &asset_folder,
+            &[
+                (IGNORE_FILE_NAME, "Thumbs.db\n"),
+                ("ship.png", "png"),
+                ("textures/ship.png", "png"),
+                ("textures/ship.psd", "psd"),
+                ("textures/Thumbs.db", "db"),
+                ("sounds/music/theme.ogg", "ogg"),
+                ("sources/ship.png", "png"),
+            ],
+        );
+        let excluded = [
+            "textures/ship.psd",
+            "textures/Thumbs.db",
+            "sources/ship.png",
+        ];
+
+        let options = IncludeOptions::new()
+            .exclude("**/*.psd")
+            .exclude("sources/**");
+        let mut collector = Collector::new(&asset_folder, &options).unwrap();
+        collector.collect(&asset_folder).unwrap();
+        let rerun_paths = collector.rerun_paths();
+        assert_eq!(
+            rerun_paths,
+            [
+                asset_folder.join(IGNORE_FILE_NAME),
+                asset_folder.clone(),
+                asset_folder.join("sounds"),
+                asset_folder.join("sounds/music"),
+                asset_folder.join("textures"),
+                asset_folder.join("ship.png"),
+                asset_folder.join("sounds/music/theme.ogg"),
+                asset_folder.join("textures/ship.png"),
+            ]
+        );
+        for file in excluded.map(|file| asset_folder.join(file)) {
+            assert!(!rerun_paths.contains(&file));
+        }
+    }
+
+    #[test]
+    fn forward_slash_keys() {
+        assert_eq!(
+            relative_path(Path::new("assets"), Path::new("assets/textures\\ship.png")).unwrap(),
+            "textures/ship.png"
+        );
+        assert_eq!(
+            relative_path(Path::new("./assets"), Path::new("./assets/sounds/bg.ogg")).unwrap(),
+            "sounds/bg.ogg"
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compression_for() {
+        let options = IncludeOptions::new()
+            .compress(Compression::Zstd)
+            .compress_extension("PNG", Compression::None)
+            .compress_path("special.png", Compression::Zstd)
+            .compress_path("dir\\special.png", Compression::Zstd);
+        assert_eq!(options.compression_for("sound.wav"), Compression::Zstd);
+        assert_eq!(options.compression_for("image.png"), Compression::None);
+        assert_eq!(options.compression_for("dir/image.png"), Compression::None);
+        assert_eq!(options.compression_for("special.png"), Compression::Zstd);
+        assert_eq!(
+            options.compression_for("dir/special.png"),
+            Compression::Zstd
+        );
+    }
+
+    #[test]
+    fn load_from_disk_cfg() {
+        assert_eq!(LoadFromDisk::DebugAssertions.cfg(), "debug_assertions");
+        assert_eq!(
+            LoadFromDisk::Feature("dev-assets".to_string()).cfg(),
+            "feature = \"dev-assets\""
+        );
+        assert_eq!(
+            LoadFromDisk::Feature("dev-assets".to_string()).env_var(),
+            "CARGO_FEATURE_DEV_ASSETS"
+        );
+    }
+
+    #[cfg(unix)]
+    fn collect(asset_folder: &Path, options: &IncludeOptions) -> Result<Vec<String>, BuildError> {
+        collect_folder(asset_folder, options, false)
+            .map(|assets| assets.into_iter().map(|(path, _)| path).collect())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks() {
+        use std::os::unix::fs::symlink;
+
+        let asset_folder = test_dir("symlinks");
+        write_files(&asset_folder, &[("textures/ship.png", "png")]);
+        symlink(asset_folder.join("textures"), asset_folder.join("linked")).unwrap();
+
+        assert_eq!(
+            collect(&asset_folder, &IncludeOptions::new()).unwrap(),
+            ["linked/ship.png", "textures/ship.png"]
+        );
+        let skip = IncludeOptions::new().symlinks(Symlinks::Skip);
+        assert_eq!(
+            collect(&asset_folder, &skip).unwrap(),
+            ["textures/ship.png"]
+        );
+
+        symlink(&asset_folder, asset_folder.join("textures/parent")).unwrap();
+        assert!(matches!(
+            collect(&asset_folder, &IncludeOptions::new()),
+            Err(BuildError::SymlinkCycle { link, .. })
+                if link == asset_folder.join("linked/parent")
+        ));
+        assert_eq!(
+            collect(&asset_folder, &skip).unwrap(),
+            ["textures/ship.png"]
+        );
+        assert_eq!(
+            all_files(&asset_folder, &asset_folder),
+            ["textures/ship.png"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_names() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let asset_folder = test_dir("non-utf8-names");
+        write_files(
+            &asset_folder,
+            &[("ship.png", "png"), ("space ship (1).png", "png")],
+        );
+        let odd = asset_folder.join(OsStr::from_bytes(b"ship\xff.png"));
+        fs::write(&odd, "png").unwrap();
+
+        assert!(matches!(
+            collect(&asset_folder, &IncludeOptions::new()),
+            Err(BuildError::NonUtf8Path(path)) if path == odd
+        ));
+        assert_eq!(
+            collect(
+                &asset_folder,
+                &IncludeOptions::new().non_utf8_names(NonUtf8Names::Skip)
+            )
+            .unwrap(),
+            ["ship.png", "space ship (1).png"]
+        );
+    }
 }
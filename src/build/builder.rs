@@ -0,0 +1,1026 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    all_files, collect_folder, error, manifest, names, portability, report, transform, usage,
+    validate, AssetLayout, BuildError, DuplicatePath, IncludeOptions, InvalidAsset, MissingAsset,
+    Portability,
+};
+use crate::{normalize::normalize_path, pack, Compression};
+
+const DEFAULT_FUNCTION_NAME: &str = "add_embasset_assets";
+
+/// Keywords, which can't be used as identifiers.
+const KEYWORDS: &[&str] = &[
+    "Self", "abstract", "as", "async", "await", "become", "box", "break", "const", "continue",
+    "crate", "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl",
+    "in", "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "static", "struct", "super", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Builder for generating a function that adds assets to [`EmbassetIo`](crate::EmbassetIo).
+///
+/// For use from a build script (`build.rs`). Allows for configuring the name and visibility of
+/// the generated function, as well as the file it is written to. Several builders can be used
+/// from the same build script, as long as they are writing to different files.
+///
+/// # Example
+///
+/// ```ignore
+/// EmbassetBuilder::new()
+///     .function_name("add_core_assets")
+///     .visibility("pub")
+///     .add_folder(Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets"))
+///     .generate()?;
+/// ```
+///
+/// and included in the source:
+///
+/// ```ignore
+/// include!(concat!(env!("OUT_DIR"), "/add_core_assets.rs"));
+/// ```
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone)]
+pub struct EmbassetBuilder {
+    file_name: Option<String>,
+    function_name: String,
+    visibility: String,
+    options: IncludeOptions,
+    folders: Vec<AssetFolder>,
+    manifest_path: Option<PathBuf>,
+    report_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+struct AssetFolder {
+    path: PathBuf,
+    /// Virtual path prefix, without leading and trailing `/`.
+    mount: String,
+    assets: FolderAssets,
+}
+
+/// Which assets to include from an [`AssetFolder`].
+#[derive(Debug, Clone)]
+enum FolderAssets {
+    /// All assets in the folder.
+    All,
+    /// The listed assets.
+    Listed(Vec<String>),
+    /// The assets listed in a file written by
+    /// [`EmbassetIo::record_usage`](crate::EmbassetIo::record_usage).
+    Used(PathBuf),
+}
+
+impl Default for EmbassetBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmbassetBuilder {
+    /// Creates a new builder, generating a private function named `add_embasset_assets` in the
+    /// file `add_embasset_assets.rs`.
+    pub fn new() -> Self {
+        EmbassetBuilder {
+            file_name: None,
+            function_name: DEFAULT_FUNCTION_NAME.to_string(),
+            visibility: String::new(),
+            options: IncludeOptions::default(),
+            folders: vec![],
+            manifest_path: None,
+            report_path: None,
+        }
+    }
+
+    /// Name of the file (in `OUT_DIR`) to write the generated code to.
+    ///
+    /// Defaults to the function name, with the extension `.rs`.
+    #[must_use]
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_string());
+        self
+    }
+
+    /// Name of the generated function.
+    #[must_use]
+    pub fn function_name(mut self, function_name: &str) -> Self {
+        self.function_name = function_name.to_string();
+        self
+    }
+
+    /// Visibility of the generated function: `pub`, `pub(crate)`, `pub(super)`, `pub(self)`,
+    /// `pub(in path)`, or empty for private.
+    ///
+    /// Defaults to private.
+    #[must_use]
+    pub fn visibility(mut self, visibility: &str) -> Self {
+        self.visibility = visibility.to_string();
+        self
+    }
+
+    /// Sets the [`IncludeOptions`] to use for all folders.
+    #[must_use]
+    pub fn options(mut self, options: IncludeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Additionally copy the asset manifest to `path`.
+    ///
+    /// The manifest is always written to `OUT_DIR`, named after the function, e.g.
+    /// `add_embasset_assets.manifest.json`. It lists the path, size and SHA-256 hash of each
+    /// embedded asset, sorted by path.
+    #[must_use]
+    pub fn manifest(mut self, path: impl AsRef<Path>) -> Self {
+        self.manifest_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Additionally copy the size report to `path`.
+    ///
+    /// The report is always written to `OUT_DIR`, named after the function, e.g.
+    /// `add_embasset_assets.report.txt`. It lists the total size of the embedded assets, the
+    /// largest assets, and the size per folder and per extension.
+    #[must_use]
+    pub fn report(mut self, path: impl AsRef<Path>) -> Self {
+        self.report_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Include *all* assets from `asset_folder`.
+    ///
+    /// See [`include_all_assets_with`](super::include_all_assets_with) for how to leave out
+    /// files.
+    #[must_use]
+    pub fn add_folder(self, asset_folder: impl AsRef<Path>) -> Self {
+        self.add_folder_at(asset_folder, "")
+    }
+
+    /// Include *all* assets from `asset_folder`, mounted under the virtual path prefix `mount`.
+    ///
+    /// E.g. with the mount `vendor/kenney`, the file `ship.png` in the folder is embedded as
+    /// `vendor/kenney/ship.png`.
+    #[must_use]
+    pub fn add_folder_at(mut self, asset_folder: impl AsRef<Path>, mount: &str) -> Self {
+        self.folders.push(AssetFolder {
+            path: asset_folder.as_ref().to_path_buf(),
+            mount: normalize_path(mount),
+            assets: FolderAssets::All,
+        });
+        self
+    }
+
+    /// Include the specified `assets` from `asset_folder`.
+    #[must_use]
+    pub fn add_assets(self, asset_folder: impl AsRef<Path>, assets: &[&str]) -> Self {
+        self.add_assets_at(asset_folder, "", assets)
+    }
+
+    /// Include the specified `assets` from `asset_folder`, mounted under the virtual path prefix
+    /// `mount`.
+    ///
+    /// See [`add_folder_at`](EmbassetBuilder::add_folder_at).
+    #[must_use]
+    pub fn add_assets_at(
+        mut self,
+        asset_folder: impl AsRef<Path>,
+        mount: &str,
+        assets: &[&str],
+    ) -> Self {
+        self.folders.push(AssetFolder {
+            path: asset_folder.as_ref().to_path_buf(),
+            mount: normalize_path(mount),
+            assets: FolderAssets::Listed(assets.iter().map(|asset| asset.to_string()).collect()),
+        });
+        self
+    }
+
+    /// Include the assets from `asset_folder` that are listed in `usage_file`, as recorded by
+    /// [`EmbassetIo::record_usage`](crate::EmbassetIo::record_usage) while playing the game.
+    ///
+    /// Recorded directories include all of the files below them. Only files included by the
+    /// [options](EmbassetBuilder::options) are used, so ignored and excluded files are left out
+    /// just like by [`include_all_assets_with`](super::include_all_assets_with). Recorded paths
+    /// that don't match an included file in the folder are ignored, as they may have been served
+    /// by something else. The
+    /// files in the folder that weren't used are listed in `OUT_DIR`, named after the function,
+    /// e.g. `add_embasset_assets.unused.txt`.
+    #[must_use]
+    pub fn add_used_assets(
+        self,
+        asset_folder: impl AsRef<Path>,
+        usage_file: impl AsRef<Path>,
+    ) -> Self {
+        self.add_used_assets_at(asset_folder, "", usage_file)
+    }
+
+    /// Include the assets from `asset_folder` that are listed in `usage_file`, mounted under the
+    /// virtual path prefix `mount`.
+    ///
+    /// Only recorded paths below `mount` are used for this folder, so the same usage file can be
+    /// used for several folders. See [`add_used_assets`](EmbassetBuilder::add_used_assets).
+    #[must_use]
+    pub fn add_used_assets_at(
+        mut self,
+        asset_folder: impl AsRef<Path>,
+        mount: &str,
+        usage_file: impl AsRef<Path>,
+    ) -> Self {
+        self.folders.push(AssetFolder {
+            path: asset_folder.as_ref().to_path_buf(),
+            mount: normalize_path(mount),
+            assets: FolderAssets::Used(usage_file.as_ref().to_path_buf()),
+        });
+        self
+    }
+
+    /// Generates the function, and writes it to the file in `OUT_DIR`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the function name is not a valid identifier or the visibility isn't valid, if any
+    /// of the specified assets
+    /// could not be found, if any assets are Git LFS pointer files, if several files would be
+    /// embedded using the same path, if asset paths aren't portable and [`Portability::Deny`] is
+    /// in effect, if an asset enum could not be generated, or if reading or writing files fails.
+    pub fn generate(&self) -> Result<(), BuildError> {
+        if !is_identifier(&self.function_name) {
+            return Err(BuildError::InvalidName {
+                name: self.function_name.clone(),
+                reason: "not a valid function name".to_string(),
+            });
+        }
+        if !is_visibility(&self.visibility) {
+            return Err(BuildError::InvalidName {
+                name: self.visibility.clone(),
+                reason: "not a valid visibility".to_string(),
+            });
+        }
+
+        let load_from_disk = self.options.load_from_disk.as_ref();
+        if let Some(load_from_disk) = load_from_disk {
+            println!("cargo:rerun-if-env-changed={}", load_from_disk.env_var());
+        }
+        // Assets loaded from disk are not compiled into the crate, so don't rerun on changes.
+        let watch = !matches!(load_from_disk, Some(load_from_disk) if load_from_disk.is_enabled());
+
+        let mut assets = vec![];
+        let mut missing = vec![];
+        let mut unused = None;
+        for folder in &self.folders {
+            let mut folder_assets = match &folder.assets {
+                FolderAssets::All => collect_folder(&folder.path, &self.options, watch)?,
+                FolderAssets::Used(usage_file) => {
+                    println!("cargo:rerun-if-changed={}", usage_file.display());
+                    let recorded =
+                        fs::read_to_string(usage_file).map_err(BuildError::io(usage_file))?;
+                    used_assets(
+                        folder,
+                        &self.options,
+                        &recorded,
+                        watch,
+                        unused.get_or_insert_with(Vec::new),
+                    )?
+                }
+                FolderAssets::Listed(listed) => {
+                    let mut folder_assets = vec![];
+                    let mut existing = None;
+                    for asset in listed {
+                        let asset = normalize_path(asset);
+                        let path = folder.path.join(&asset);
+                        if path.is_file() {
+                            if watch {
+                                println!("cargo:rerun-if-changed={}", path.display());
+                            }
+                            folder_assets.push((asset, path));
+                        } else {
+                            let existing = existing
+                                .get_or_insert_with(|| all_files(&folder.path, &folder.path));
+                            missing.push(MissingAsset {
+                                path,
+                                suggestion: error::suggest(
+                                    &asset,
+                                    existing.iter().map(String::as_str),
+                                ),
+                            });
+                        }
+                    }
+                    if watch {
+                        println!("cargo:rerun-if-changed={}", folder.path.display());
+                    }
+                    folder_assets
+                }
+            };
+            if !folder.mount.is_empty() {
+                for (path, _) in folder_assets.iter_mut() {
+                    *path = format!("{}/{}", folder.mount, path);
+                }
+            }
+            assets.append(&mut folder_assets);
+        }
+        if !missing.is_empty() {
+            return Err(BuildError::MissingAssets(missing));
+        }
+        validate::check_lfs_pointers(assets.iter().map(|(_, source)| source.as_path()))?;
+
+        let out_dir =
+            PathBuf::from(env::var_os("OUT_DIR").ok_or(BuildError::MissingEnvVar("OUT_DIR"))?);
+        let file_name = self
+            .file_name
+            .clone()
+            .unwrap_or_else(|| format!("{}.rs", self.function_name));
+        // Builders writing to different files may generate functions with the same name.
+        let blob_dir = out_dir
+            .join(Path::new(&file_name).with_extension(""))
+            .join(&self.function_name);
+        let transform_dir = blob_dir.join("transformed");
+        if let Some(unused) = unused {
+            let unused_file = out_dir.join(format!("{}.unused.txt", self.function_name));
+            let mut content = String::new();
+            for file in &unused {
+                content.push_str(&format!("{}\n", file.display()));
+            }
+            write_if_changed(&unused_file, content)?;
+            if !unused.is_empty() {
+                println!(
+                    "cargo:warning={} unused assets are not embedded, see {}",
+                    unused.len(),
+                    unused_file.display()
+                );
+            }
+        }
+        let mut assets = assets
+            .into_iter()
+            .map(|(path, source)| match self.options.transform_for(&path) {
+                Some(transform) => {
+                    let (path, file) = transform.apply(&transform_dir, &path, &source)?;
+                    Ok((path, source, file))
+                }
+                None => Ok((path, source.clone(), source)),
+            })
+            .collect::<Result<Vec<_>, BuildError>>()?;
+        transform::remove_unused(
+            &transform_dir,
+            assets.iter().map(|(_, _, file)| file.as_path()),
+        )?;
+        check_collisions(
+            assets
+                .iter()
+                .map(|(path, source, _)| (path.as_str(), source.as_path())),
+        )?;
+        assets.sort();
+        if self.options.portability != Portability::Allow {
+            self.options.portability.report(portability::check_paths(
+                assets.iter().map(|(path, _, _)| path.as_str()),
+                self.options
+                    .max_path_length
+                    .unwrap_or(portability::DEFAULT_MAX_PATH_LENGTH),
+            ))?;
+        }
+
+        let variant_names = match &self.options.asset_enum {
+            Some(_) => Some(names::variant_names(
+                assets.iter().map(|(path, _, _)| path.as_str()),
+            )?),
+            None => None,
+        };
+
+        let mut invalid = vec![];
+        // Blobs by content hash and requested compression, so identical assets share a blob.
+        let mut blobs: HashMap<(String, Compression), (Compression, PathBuf, u64)> = HashMap::new();
+        let embedded = assets
+            .into_iter()
+            .map(|(path, source, file)| {
+                let data = fs::read(&file).map_err(BuildError::io(&file))?;
+                if let Err(reason) = self.options.validate_asset(&path, &data) {
+                    invalid.push(InvalidAsset {
+                        path: source,
+                        reason,
+                    });
+                }
+                let sha256 = manifest::sha256(&data);
+                let key = (sha256.clone(), self.options.compression_for(&path));
+                let duplicate = blobs.contains_key(&key);
+                let (compression, blob, embedded_size) = match blobs.get(&key) {
+                    Some(blob) => blob.clone(),
+                    None => {
+                        let (compression, blob) =
+                            match compress_asset(&blob_dir, &path, &data, key.1)? {
+                                Some((compression, blob)) => (compression, blob),
+                                None => (Compression::None, file.clone()),
+                            };
+                        let embedded_size = if compression == Compression::None {
+                            data.len() as u64
+                        } else {
+                            fs::metadata(&blob).map_err(BuildError::io(&blob))?.len()
+                        };
+                        blobs.insert(key, (compression, blob.clone(), embedded_size));
+                        (compression, blob, embedded_size)
+                    }
+                };
+                let source = match load_from_disk {
+                    Some(_) => fs::canonicalize(&file).map_err(BuildError::io(&file))?,
+                    None => file,
+                };
+                Ok(EmbeddedAsset {
+                    size: data.len() as u64,
+                    embedded_size,
+                    sha256,
+                    path,
+                    compression,
+                    blob,
+                    source,
+                    duplicate,
+                })
+            })
+            .collect::<Result<Vec<_>, BuildError>>()?;
+        if !invalid.is_empty() {
+            return Err(BuildError::InvalidAssets(invalid));
+        }
+
+        let report = report::to_text(&embedded);
+        let report_file = out_dir.join(format!("{}.report.txt", self.function_name));
+        write_if_changed(&report_file, &report)?;
+        if let Some(report_path) = &self.report_path {
+            write_if_changed(report_path, &report)?;
+        }
+        let violations = report::check_budgets(
+            &embedded,
+            self.options.max_total_size,
+            self.options.max_asset_size,
+        );
+        if !violations.is_empty() {
+            if self.options.fail_over_budget {
+                return Err(BuildError::OverBudget(violations));
+            }
+            for violation in violations {
+                println!("cargo:warning={}", violation);
+            }
+        }
+
+        let mut code = String::new();
+        let visibility = if self.visibility.is_empty() {
+            String::new()
+        } else {
+            format!("{} ", self.visibility)
+        };
+        code.push_str(&format!(
+            "{}fn {}(#[allow(unused)] in_memory: &mut bevy_embasset::EmbassetIo){{\n",
+            visibility, self.function_name
+        ));
+        let embed_code = match self.options.layout {
+            AssetLayout::Individual => {
+                let shared = shared_blobs(&embedded);
+                let mut code = shared_blobs_code(&shared);
+                for asset in &embedded {
+                    code.push_str(&embed_asset(asset, &blob_expr(asset, &shared)));
+                }
+                code
+            }
+            AssetLayout::Pack => {
+                let pack_file = blob_dir.join("assets.pack");
+                let blobs = embedded
+                    .iter()
+                    .map(|asset| fs::read(&asset.blob).map_err(BuildError::io(&asset.blob)))
+                    .collect::<Result<Vec<_>, BuildError>>()?;
+                let pack = pack::write_pack(
+                    &embedded
+                        .iter()
+                        .zip(&blobs)
+                        .map(|(asset, blob)| {
+                            (asset.path.as_str(), asset.compression, blob.as_slice())
+                        })
+                        .collect::<Vec<_>>(),
+                );
+                write_if_changed(&pack_file, pack)?;
+                format!(
+                    "    in_memory.add_pack(bevy_embasset::AssetPack::new(include_bytes!({:?})).expect(\"invalid asset pack\"));\n",
+                    pack_file.to_string_lossy()
+                )
+            }
+            AssetLayout::Table => table_code(&embedded),
+        };
+        match load_from_disk {
+            Some(load_from_disk) => {
+                let cfg = load_from_disk.cfg();
+                code.push_str(&format!("    #[cfg({})]\n    {{\n", cfg));
+                for asset in &embedded {
+                    code.push_str(&format!(
+                        "        in_memory.add_disk_asset(std::path::Path::new({:?}), std::path::Path::new({:?}));\n",
+                        asset.path,
+                        asset.source.to_string_lossy()
+                    ));
+                }
+                code.push_str(&format!("    }}\n    #[cfg(not({}))]\n    {{\n", cfg));
+                for line in embed_code.lines() {
+                    code.push_str(&format!("    {}\n", line));
+                }
+                code.push_str("    }\n");
+            }
+            None => code.push_str(&embed_code),
+        }
+        code.push_str("}\n");
+
+        if let (Some(asset_enum), Some(variant_names)) = (&self.options.asset_enum, variant_names) {
+            code.push_str(&enum_code(
+                &visibility,
+                asset_enum,
+                &self.function_name,
+                embedded
+                    .iter()
+                    .map(|asset| asset.path.as_str())
+                    .zip(&variant_names),
+            ));
+        }
+
+        write_if_changed(&out_dir.join(file_name), code)?;
+
+        let manifest = manifest::to_json(&embedded);
+        let manifest_file = out_dir.join(format!("{}.manifest.json", self.function_name));
+        write_if_changed(&manifest_file, &manifest)?;
+        if let Some(manifest_path) = &self.manifest_path {
+            write_if_changed(manifest_path, &manifest)?;
+        }
+        Ok(())
+    }
+}
+
+/// An asset, as it will be embedded.
+#[derive(Debug)]
+pub(super) struct EmbeddedAsset {
+    /// Path used for loading the asset.
+    pub path: String,
+    /// Size of the asset, after any transform.
+    pub size: u64,
+    /// Size of the asset as embedded, i.e. after any compression.
+    pub embedded_size: u64,
+    /// Hex encoded SHA-256 hash of the asset, after any transform.
+    pub sha256: String,
+    pub compression: Compression,
+    /// The file to embed - either the original file, or a processed copy of it.
+    pub blob: PathBuf,
+    /// The file loaded when loading from disk - the original file, or the transformed output.
+    /// Absolute if the asset may be loaded from disk.
+    pub source: PathBuf,
+    /// The content is identical to that of an earlier asset, and is embedded only once.
+    pub duplicate: bool,
+}
+
+/// Code adding an [`AssetTable`](crate::AssetTable) with the assets to the `EmbassetIo` named
+/// `in_memory`.
+fn table_code(embedded: &[EmbeddedAsset]) -> String {
+    let shared = shared_blobs(embedded);
+    let mut assets = phf_codegen::Map::new();
+    assets.phf_path("bevy_embasset::phf");
+    for asset in embedded {
+        assets.entry(
+            asset.path.as_str(),
+            &format!(
+                "bevy_embasset::StaticAsset::new({}, bevy_embasset::Compression::{:?})",
+                blob_expr(asset, &shared),
+                asset.compression
+            ),
+        );
+    }
+
+    let mut directories: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for asset in embedded {
+        let mut child = asset.path.as_str();
+        while let Some((parent, _)) = child.rsplit_once('/') {
+            directories.entry(parent).or_default().insert(child);
+            child = parent;
+        }
+        directories.entry("").or_default().insert(child);
+    }
+    let mut directory_map = phf_codegen::Map::new();
+    directory_map.phf_path("bevy_embasset::phf");
+    for (dir, paths) in &directories {
+        directory_map.entry(*dir, &format!("&{:?}", paths.iter().collect::<Vec<_>>()));
+    }
+
+    format!(
+        "{}    static ASSETS: bevy_embasset::phf::Map<&'static str, bevy_embasset::StaticAsset> = {};\n    static DIRECTORIES: bevy_embasset::phf::Map<&'static str, &'static [&'static str]> = {};\n    in_memory.add_table(bevy_embasset::AssetTable::new(&ASSETS, &DIRECTORIES));\n",
+        shared_blobs_code(&shared),
+        assets.build(),
+        directory_map.build()
+    )
+}
+
+/// Code invoking the [`assets!`](crate::assets) macro for the enum `asset_enum`, with a variant for
+/// each of `assets`, given as the path and variant name, and the `AssetIo` adding them using the
+/// function `function_name`.
+///
+/// The enum and `AssetIo` get the same `visibility` as the function, given with a trailing space
+/// unless private.
+fn enum_code<'a>(
+    visibility: &str,
+    asset_enum: &str,
+    function_name: &str,
+    assets: impl IntoIterator<Item = (&'a str, &'a String)>,
+) -> String {
+    let mut code = format!(
+        "bevy_embasset::assets!(\n    {}enum {} {{\n",
+        visibility, asset_enum
+    );
+    for (path, variant) in assets {
+        code.push_str(&format!("        {} = {:?},\n", variant, path));
+    }
+    code.push_str(&format!(
+        "    }},\n    {}struct {}Io {{\n        init = {}\n    }}\n);\n",
+        visibility, asset_enum, function_name
+    ));
+    code
+}
+
+/// Names of the blobs shared by several assets, in order of first use.
+fn shared_blobs(embedded: &[EmbeddedAsset]) -> Vec<(&Path, String)> {
+    let duplicated = embedded
+        .iter()
+        .filter(|asset| asset.duplicate)
+        .map(|asset| asset.blob.as_path())
+        .collect::<HashSet<_>>();
+    let mut shared: Vec<(&Path, String)> = vec![];
+    for asset in embedded {
+        if duplicated.contains(asset.blob.as_path())
+            && !shared.iter().any(|(blob, _)| *blob == asset.blob)
+        {
+            shared.push((&asset.blob, format!("BLOB{}", shared.len())));
+        }
+    }
+    shared
+}
+
+/// Code declaring the shared blobs as statics.
+fn shared_blobs_code(shared: &[(&Path, String)]) -> String {
+    shared
+        .iter()
+        .map(|(blob, name)| {
+            format!(
+                "    static {}: &[u8] = include_bytes!({:?});\n",
+                name,
+                blob.to_string_lossy()
+            )
+        })
+        .collect()
+}
+
+/// Expression for the embedded content of `asset`.
+fn blob_expr(asset: &EmbeddedAsset, shared: &[(&Path, String)]) -> String {
+    match shared.iter().find(|(blob, _)| *blob == asset.blob) {
+        Some((_, name)) => name.clone(),
+        None => format!("include_bytes!({:?})", asset.blob.to_string_lossy()),
+    }
+}
+
+/// Code line adding `asset`, with the content given by `blob`, to the `EmbassetIo` named
+/// `in_memory`.
+fn embed_asset(asset: &EmbeddedAsset, blob: &str) -> String {
+    if asset.compression == Compression::None {
+        format!(
+            "    in_memory.add_embedded_asset(std::path::Path::new({:?}), {});\n",
+            asset.path, blob
+        )
+    } else {
+        format!(
+            "    in_memory.add_compressed_asset(std::path::Path::new({:?}), bevy_embasset::Compression::{:?}, {});\n",
+            asset.path, asset.compression, blob
+        )
+    }
+}
+
+/// The files included from `folder` by `options` that are used according to `recorded`, along with
+/// the path used for embedding them. The included files that weren't used are added to `unused`.
+///
+/// The files are collected just like by
+/// [`include_all_assets_with`](super::include_all_assets_with), so ignored and excluded files are
+/// never embedded, even when in a recorded directory.
+fn used_assets(
+    folder: &AssetFolder,
+    options: &IncludeOptions,
+    recorded: &str,
+    watch: bool,
+    unused: &mut Vec<PathBuf>,
+) -> Result<Vec<(String, PathBuf)>, BuildError> {
+    let included = collect_folder(&folder.path, options, watch)?;
+    let files = included
+        .iter()
+        .map(|(asset, _)| asset.clone())
+        .collect::<Vec<_>>();
+    let (used, folder_unused) = usage::used_files(&files, &folder.mount, recorded);
+    unused.extend(folder_unused.iter().map(|file| folder.path.join(file)));
+    Ok(included
+        .into_iter()
+        .filter(|(asset, _)| used.binary_search(asset).is_ok())
+        .collect())
+}
+
+/// Writes `content` to `path`, creating any missing parent directories.
+///
+/// The file is left untouched if it already holds `content`, so its modification time only
+/// changes along with the content.
+fn write_if_changed(path: &Path, content: impl AsRef<[u8]>) -> Result<(), BuildError> {
+    let content = content.as_ref();
+    if matches!(fs::read(path), Ok(existing) if existing == content) {
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(BuildError::io(parent))?;
+    }
+    fs::write(path, content).map_err(BuildError::io(path))
+}
+
+/// Fails if several files are embedded using the same path.
+fn check_collisions<'a>(
+    assets: impl IntoIterator<Item = (&'a str, &'a Path)>,
+) -> Result<(), BuildError> {
+    let mut sources: HashMap<&str, &Path> = HashMap::new();
+    let mut duplicates = vec![];
+    for (path, fullpath) in assets {
+        if let Some(other) = sources.insert(path, fullpath) {
+            duplicates.push(DuplicatePath {
+                path: path.to_string(),
+                first: other.to_path_buf(),
+                second: fullpath.to_path_buf(),
+            });
+        }
+    }
+    if duplicates.is_empty() {
+        Ok(())
+    } else {
+        Err(BuildError::DuplicatePaths(duplicates))
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+        && name != "_"
+        && !KEYWORDS.contains(&name)
+}
+
+/// Checks if `visibility` is a visibility modifier, or empty for private.
+fn is_visibility(visibility: &str) -> bool {
+    let restriction = match visibility.strip_prefix("pub") {
+        None => return visibility.is_empty(),
+        Some("") => return true,
+        Some(restriction) => restriction.trim_start(),
+    };
+    let restriction = match restriction
+        .strip_prefix('(')
+        .and_then(|restriction| restriction.strip_suffix(')'))
+    {
+        Some(restriction) => restriction.trim(),
+        None => return false,
+    };
+    match restriction {
+        "crate" | "super" | "self" => true,
+        restriction => match restriction.strip_prefix("in ") {
+            Some(path) => path.trim().split("::").enumerate().all(|(i, segment)| {
+                let segment = segment.trim();
+                is_identifier(segment)
+                    || segment == "super"
+                    || (i == 0 && matches!(segment, "crate" | "self"))
+            }),
+            None => false,
+        },
+    }
+}
+
+/// Writes a compressed copy of the asset to `blob_dir`, returning its path.
+///
+/// Returns `None` if the asset should be embedded as is, either because no compression was
+/// requested, or because the asset didn't shrink when compressed.
+fn compress_asset(
+    blob_dir: &Path,
+    path: &str,
+    data: &[u8],
+    compression: Compression,
+) -> Result<Option<(Compression, PathBuf)>, BuildError> {
+    if compression == Compression::None {
+        return Ok(None);
+    }
+    let blob = blob_dir.join(format!("{}.{}", path, compression.extension()));
+    let compressed = compression.compress(data).map_err(BuildError::io(&blob))?;
+    if compressed.len() >= data.len() {
+        return Ok(None);
+    }
+    write_if_changed(&blob, compressed)?;
+    Ok(Some((compression, blob)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{test_dir, write_files};
+
+    #[test]
+    fn function_names() {
+        assert!(is_identifier("add_core_assets"));
+        assert!(is_identifier("_assets2"));
+        assert!(!is_identifier("_"));
+        assert!(!is_identifier("2assets"));
+        assert!(!is_identifier("add-assets"));
+        assert!(!is_identifier(""));
+        assert!(!is_identifier("match"));
+        assert!(!is_identifier("Self"));
+    }
+
+    #[test]
+    fn enum_visibility() {
+        let variant = "Icon".to_string();
+        let code = enum_code("pub(crate) ", "A", "add_a", [("icon.png", &variant)]);
+        assert!(code.contains("    pub(crate) enum A {\n        Icon = \"icon.png\",\n"));
+        assert!(code.contains("    pub(crate) struct AIo {\n        init = add_a\n"));
+        let code = enum_code("", "A", "add_a", [("icon.png", &variant)]);
+        assert!(code.contains("    enum A {"));
+        assert!(code.contains("    struct AIo {"));
+    }
+
+    #[test]
+    fn visibilities() {
+        assert!(is_visibility(""));
+        assert!(is_visibility("pub"));
+        assert!(is_visibility("pub(crate)"));
+        assert!(is_visibility("pub(super)"));
+        assert!(is_visibility("pub(in crate::assets)"));
+        assert!(is_visibility("pub(in super::super)"));
+        assert!(!is_visibility("pub(in)"));
+        assert!(!is_visibility("pub(crate) fn x() {} //"));
+        assert!(!is_visibility("pub(in crate::fn)"));
+        assert!(!is_visibility("public"));
+        assert!(!is_visibility("crate"));
+    }
+
+    #[test]
+    fn mounts() {
+        assert_eq!(normalize_path(""), "");
+        assert_eq!(normalize_path("/"), "");
+        assert_eq!(normalize_path("ui/"), "ui");
+        assert_eq!(normalize_path("./vendor//kenney/"), "vendor/kenney");
+        assert_eq!(normalize_path("vendor\\kenney"), "vendor/kenney");
+    }
+
+    #[test]
+    fn collisions() {
+        let assets = [
+            ("ui/ship.png".to_string(), PathBuf::from("/a/ship.png")),
+            ("ship.png".to_string(), PathBuf::from("/b/ship.png")),
+        ];
+        assert!(check_collisions(assets.iter().map(|(p, s)| (p.as_str(), s.as_path()))).is_ok());
+
+        let assets = [
+            ("ui/ship.png".to_string(), PathBuf::from("/a/ship.png")),
+            ("ui/ship.png".to_string(), PathBuf::from("/b/ui/ship.png")),
+        ];
+        let err =
+            check_collisions(assets.iter().map(|(p, s)| (p.as_str(), s.as_path()))).unwrap_err();
+        assert!(matches!(
+            err,
+            BuildError::DuplicatePaths(duplicates) if duplicates == vec![DuplicatePath {
+                path: "ui/ship.png".to_string(),
+                first: PathBuf::from("/a/ship.png"),
+                second: PathBuf::from("/b/ui/ship.png"),
+            }]
+        ));
+    }
+
+    #[test]
+    fn used_assets_are_filtered() {
+        let dir = test_dir("used-assets");
+        write_files(
+            &dir,
+            &[
+                (super::super::IGNORE_FILE_NAME, "*.psd\n"),
+                ("levels/1.ron", "1"),
+                ("levels/1.psd", "psd"),
+                ("levels/unused.ron", "2"),
+                ("secret/key.txt", "key"),
+            ],
+        );
+        let folder = AssetFolder {
+            path: dir.clone(),
+            mount: String::new(),
+            assets: FolderAssets::All,
+        };
+        let options = IncludeOptions::new().exclude("secret/**");
+
+        let mut unused = vec![];
+        let used = used_assets(
+            &folder,
+            &options,
+            "levels/1.ron\nlevels/1.psd\nsecret/key.txt\n",
+            false,
+            &mut unused,
+        )
+        .unwrap();
+        assert_eq!(
+            used,
+            [("levels/1.ron".to_string(), dir.join("levels/1.ron"))]
+        );
+        assert_eq!(unused, [dir.join("levels/unused.ron")]);
+
+        let used = used_assets(&folder, &options, "levels/\n", false, &mut vec![]).unwrap();
+        assert_eq!(
+            used,
+            [
+                ("levels/1.ron".to_string(), dir.join("levels/1.ron")),
+                (
+                    "levels/unused.ron".to_string(),
+                    dir.join("levels/unused.ron")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn report_all_missing_assets() {
+        let err = EmbassetBuilder::new()
+            .add_assets(
+                "./assets_example",
+                &["assets/.keepm", "build.rs", "does-not-exist.xyz"],
+            )
+            .generate()
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            BuildError::MissingAssets(missing) if missing == vec![
+                MissingAsset {
+                    path: PathBuf::from("./assets_example/assets/.keepm"),
+                    suggestion: Some("assets/.keepme".to_string()),
+                },
+                MissingAsset {
+                    path: PathBuf::from("./assets_example/does-not-exist.xyz"),
+                    suggestion: None,
+                },
+            ]
+        ));
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn incompressible_asset_is_embedded_as_is() {
+        let out_dir = test_dir("incompressible-asset");
+
+        let (compression, blob) = compress_asset(
+            &out_dir,
+            "compressible.txt",
+            "text ".repeat(100).as_bytes(),
+            Compression::Deflate,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(compression, Compression::Deflate);
+        assert!(fs::metadata(blob).unwrap().len() < 500);
+
+        assert!(
+            compress_asset(&out_dir, "incompressible.bin", &[42], Compression::Deflate)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn unchanged_files_are_not_rewritten() {
+        let file = test_dir("unchanged-files").join("nested/generated.rs");
+        write_if_changed(&file, "fn a() {}").unwrap();
+        let modified = fs::metadata(&file).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        write_if_changed(&file, "fn a() {}").unwrap();
+        assert_eq!(fs::metadata(&file).unwrap().modified().unwrap(), modified);
+        write_if_changed(&file, "fn b() {}").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "fn b() {}");
+        assert_ne!(fs::metadata(&file).unwrap().modified().unwrap(), modified);
+    }
+
+    #[test]
+    fn directory_index() {
+        let embedded = ["a.png", "ui/b.png", "ui/icons/c.png"]
+            .iter()
+            .map(|path| EmbeddedAsset {
+                path: path.to_string(),
+                size: 0,
+                embedded_size: 0,
+                sha256: String::new(),
+                compression: Compression::None,
+                blob: PathBuf::from(path),
+                source: PathBuf::from(path),
+                duplicate: false,
+            })
+            .collect::<Vec<_>>();
+        let code = table_code(&embedded);
+        assert!(code.contains(r#"("ui", &["ui/b.png", "ui/icons"])"#));
+        assert!(code.contains(r#"("ui/icons", &["ui/icons/c.png"])"#));
+        assert!(code.contains(r#"("", &["a.png", "ui"])"#));
+        assert_eq!(code, table_code(&embedded));
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use super::{BudgetViolation, PortabilityIssue};
+
+/// Errors reported by the build helpers.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug)]
+pub enum BuildError {
+    /// An I/O operation on `path` failed.
+    Io {
+        /// The file or directory being accessed.
+        path: PathBuf,
+        /// The underlying error.
+        source: io::Error,
+    },
+    /// An environment variable, normally set by Cargo, is missing.
+    MissingEnvVar(&'static str),
+    /// One or more of the listed assets could not be found.
+    MissingAssets(Vec<MissingAsset>),
+    /// A name could not be used, e.g. an invalid function name.
+    InvalidName {
+        /// The offending name.
+        name: String,
+        /// Why the name is invalid.
+        reason: String,
+    },
+    /// The name of a file or directory in an asset folder isn't valid UTF-8, and
+    /// [`NonUtf8Names::Reject`](super::NonUtf8Names::Reject) is in effect.
+    NonUtf8Path(PathBuf),
+    /// Following a symlink in an asset folder leads back to a directory being traversed.
+    SymlinkCycle {
+        /// The symlink.
+        link: PathBuf,
+        /// The directory it leads back to.
+        target: PathBuf,
+    },
+    /// A glob pattern, or a line in an ignore file, is invalid.
+    InvalidPattern {
+        /// The offending pattern.
+        pattern: String,
+        /// Why the pattern is invalid.
+        reason: String,
+    },
+    /// Several files would be embedded using the same path.
+    DuplicatePaths(Vec<DuplicatePath>),
+    /// One or more asset paths won't work on all platforms, and
+    /// [`Portability::Deny`](super::Portability::Deny) is in effect.
+    NotPortable(Vec<PortabilityIssue>),
+    /// One or more assets are Git LFS pointer files, rather than the actual content.
+    LfsPointers(Vec<PathBuf>),
+    /// One or more assets failed validation.
+    InvalidAssets(Vec<InvalidAsset>),
+    /// The embedded assets exceed the size budgets, and the build was set to fail if so.
+    OverBudget(Vec<BudgetViolation>),
+    /// A transform failed.
+    Transform {
+        /// The file being transformed.
+        path: PathBuf,
+        /// The error reported by the transform.
+        reason: String,
+    },
+}
+
+/// An asset that could not be found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingAsset {
+    /// The full path of the missing asset.
+    pub path: PathBuf,
+    /// An existing asset with a similar name, if any.
+    pub suggestion: Option<String>,
+}
+
+/// An asset that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidAsset {
+    /// The file holding the asset.
+    pub path: PathBuf,
+    /// Why the asset is invalid, e.g. a parse error.
+    pub reason: String,
+}
+
+/// Two files that would be embedded using the same path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicatePath {
+    /// The path the files would be embedded as.
+    pub path: String,
+    /// The first file.
+    pub first: PathBuf,
+    /// The second file.
+    pub second: PathBuf,
+}
+
+impl BuildError {
+    pub(super) fn io(path: impl AsRef<Path>) -> impl FnOnce(io::Error) -> BuildError {
+        let path = path.as_ref().to_path_buf();
+        move |source| BuildError::Io { path, source }
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            BuildError::MissingEnvVar(var) => {
+                write!(f, "Environment variable {} is not set", var)
+            }
+            BuildError::MissingAssets(missing) => {
+                write!(f, "Assets not found: ")?;
+                for (i, asset) in missing.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", asset.path.display())?;
+                    if let Some(suggestion) = &asset.suggestion {
+                        write!(f, " (did you mean {:?}?)", suggestion)?;
+                    }
+                }
+                Ok(())
+            }
+            BuildError::InvalidName { name, reason } => {
+                write!(f, "Invalid name {:?}: {}", name, reason)
+            }
+            BuildError::NonUtf8Path(path) => write!(f, "Path is not valid UTF-8: {:?}", path),
+            BuildError::SymlinkCycle { link, target } => write!(
+                f,
+                "Symlink {} leads back to {}",
+                link.display(),
+                target.display()
+            ),
+            BuildError::InvalidPattern { pattern, reason } => {
+                write!(f, "Invalid pattern {:?}: {}", pattern, reason)
+            }
+            BuildError::DuplicatePaths(duplicates) => {
+                write!(f, "Colliding asset paths: ")?;
+                for (i, duplicate) in duplicates.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(
+                        f,
+                        "{:?} is provided by both {} and {}",
+                        duplicate.path,
+                        duplicate.first.display(),
+                        duplicate.second.display()
+                    )?;
+                }
+                Ok(())
+            }
+            BuildError::NotPortable(issues) => {
+                write!(f, "Non-portable asset paths: ")?;
+                for (i, issue) in issues.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", issue)?;
+                }
+                Ok(())
+            }
+            BuildError::LfsPointers(pointers) => {
+                write!(
+                    f,
+                    "Git LFS pointer files found instead of assets (run `git lfs pull`): "
+                )?;
+                for (i, pointer) in pointers.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", pointer.display())?;
+                }
+                Ok(())
+            }
+            BuildError::InvalidAssets(invalid) => {
+                write!(f, "Invalid assets: ")?;
+                for (i, asset) in invalid.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}: {}", asset.path.display(), asset.reason)?;
+                }
+                Ok(())
+            }
+            BuildError::OverBudget(violations) => {
+                write!(f, "Over budget: ")?;
+                for (i, violation) in violations.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "; ")?;
+                    }
+                    write!(f, "{}", violation)?;
+                }
+                Ok(())
+            }
+            BuildError::Transform { path, reason } => {
+                write!(f, "Failed to transform {}: {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the candidate most similar to `name`, if any is close enough to be a likely typo.
+pub(super) fn suggest<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let lowercase = name.to_lowercase();
+    let max_distance = (name.chars().count() / 3).max(2);
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let distance = if candidate.to_lowercase() == lowercase {
+                0
+            } else {
+                strsim::levenshtein(name, candidate)
+            };
+            (distance, candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn suggestions() {
+        let candidates = ["textures/ship.png", "textures/shot.png", "sounds/bg.ogg"];
+        assert_eq!(
+            suggest("textures/shp.png", candidates).as_deref(),
+            Some("textures/ship.png")
+        );
+        assert_eq!(
+            suggest("Textures/Ship.PNG", candidates).as_deref(),
+            Some("textures/ship.png")
+        );
+        assert_eq!(
+            suggest("sounds/bg.wav", candidates).as_deref(),
+            Some("sounds/bg.ogg")
+        );
+        assert_eq!(suggest("music/theme.ogg", candidates), None);
+    }
+
+    #[test]
+    fn display_missing_assets() {
+        let err = BuildError::MissingAssets(vec![
+            MissingAsset {
+                path: PathBuf::from("assets/shp.png"),
+                suggestion: Some("ship.png".to_string()),
+            },
+            MissingAsset {
+                path: PathBuf::from("assets/theme.ogg"),
+                suggestion: None,
+            },
+        ]);
+        assert_eq!(
+            err.to_string(),
+            "Assets not found: assets/shp.png (did you mean \"ship.png\"?), assets/theme.ogg"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::path::Path;
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use super::BuildError;
+
+/// Name of the file holding ignore rules (gitignore syntax) for an asset folder.
+///
+/// The file must be placed in the root of the asset folder, and is never embedded itself.
+pub const IGNORE_FILE_NAME: &str = ".embassetignore";
+
+/// Decides which files from an asset folder should be embedded.
+pub(super) struct AssetFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    /// Directories excluded as a whole, by exclude patterns ending in `/**`.
+    exclude_dirs: GlobSet,
+    ignore: Option<Gitignore>,
+}
+
+impl AssetFilter {
+    pub(super) fn new(
+        asset_folder: &Path,
+        include: &[String],
+        exclude: &[String],
+    ) -> Result<Self, BuildError> {
+        let ignore_file = asset_folder.join(IGNORE_FILE_NAME);
+        let ignore = if ignore_file.is_file() {
+            let mut builder = GitignoreBuilder::new(asset_folder);
+            if let Some(err) = builder.add(&ignore_file) {
+                return Err(BuildError::InvalidPattern {
+                    pattern: ignore_file.display().to_string(),
+                    reason: err.to_string(),
+                });
+            }
+            Some(builder.build().map_err(|err| BuildError::InvalidPattern {
+                pattern: ignore_file.display().to_string(),
+                reason: err.to_string(),
+            })?)
+        } else {
+            None
+        };
+
+        Ok(AssetFilter {
+            include: if include.is_empty() {
+                None
+            } else {
+                Some(build_globset(include)?)
+            },
+            exclude: build_globset(exclude)?,
+            exclude_dirs: build_globset(
+                &exclude
+                    .iter()
+                    .filter_map(|pattern| pattern.strip_suffix("/**"))
+                    .filter(|dir| !dir.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>(),
+            )?,
+            ignore,
+        })
+    }
+
+    /// Checks if a directory, and thereby all of its content, is excluded or ignored.
+    ///
+    /// `path` must be relative to the asset folder.
+    pub(super) fn is_ignored_dir(&self, path: &Path) -> bool {
+        self.exclude_dirs.is_match(path)
+            || self
+                .ignore
+                .as_ref()
+                .map(|ignore| ignore.matched(path, true).is_ignore())
+                .unwrap_or(false)
+    }
+
+    /// Checks if a file should be embedded.
+    ///
+    /// `path` must be relative to the asset folder.
+    pub(super) fn is_included_file(&self, path: &Path) -> bool {
+        if path == Path::new(IGNORE_FILE_NAME) {
+            return false;
+        }
+        if let Some(include) = &self.include {
+            if !include.is_match(path) {
+                return false;
+            }
+        }
+        if self.exclude.is_match(path) {
+            return false;
+        }
+        !self
+            .ignore
+            .as_ref()
+            .map(|ignore| ignore.matched_path_or_any_parents(path, false).is_ignore())
+            .unwrap_or(false)
+    }
+}
+
+fn build_globset(patterns: &[String]) -> Result<GlobSet, BuildError> {
+    let invalid_pattern = |pattern: &str| {
+        let pattern = pattern.to_string();
+        move |err: globset::Error| BuildError::InvalidPattern {
+            pattern,
+            reason: err.to_string(),
+        }
+    };
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).map_err(invalid_pattern(pattern))?);
+    }
+    builder
+        .build()
+        .map_err(invalid_pattern(&patterns.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{AssetFilter, BuildError, IGNORE_FILE_NAME};
+    use crate::test_util::{test_dir, write_files};
+
+    #[test]
+    fn include_and_exclude() {
+        let filter = AssetFilter::new(
+            Path::new("does-not-exist"),
+            &["*.png".to_string(), "*.ogg".to_string()],
+            &["drafts/**".to_string()],
+        )
+        .unwrap();
+        assert!(filter.is_included_file(Path::new("ship.png")));
+        assert!(filter.is_included_file(Path::new("sounds/bg.ogg")));
+        assert!(!filter.is_included_file(Path::new("ship.psd")));
+        assert!(!filter.is_included_file(Path::new("drafts/ship.png")));
+        assert!(!filter.is_included_file(Path::new(IGNORE_FILE_NAME)));
+        assert!(filter.is_ignored_dir(Path::new("drafts")));
+        assert!(!filter.is_ignored_dir(Path::new("sounds")));
+    }
+
+    #[test]
+    fn ignore_file() {
+        let asset_folder = test_dir("ignore-file");
+        write_files(
+            &asset_folder,
+            &[(
+                IGNORE_FILE_NAME,
+                "*.psd\nThumbs.db\n*~\nsource/\n!keep.psd\n",
+            )],
+        );
+
+        let filter = AssetFilter::new(&asset_folder, &[], &[]).unwrap();
+        assert!(filter.is_included_file(Path::new("ship.png")));
+        assert!(filter.is_included_file(Path::new("keep.psd")));
+        assert!(!filter.is_included_file(Path::new("ship.psd")));
+        assert!(!filter.is_included_file(Path::new("textures/Thumbs.db")));
+        assert!(!filter.is_included_file(Path::new("level.ron~")));
+        assert!(!filter.is_included_file(Path::new("source/ship.png")));
+        assert!(filter.is_ignored_dir(Path::new("source")));
+        assert!(!filter.is_ignored_dir(Path::new("textures")));
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(matches!(
+            AssetFilter::new(Path::new("does-not-exist"), &[], &["a[".to_string()]),
+            Err(BuildError::InvalidPattern { pattern, .. }) if pattern == "a["
+        ));
+    }
+}
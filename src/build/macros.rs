@@ -0,0 +1,415 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use super::{
+    portability::{self, Portability},
+    validate, BuildError,
+};
+use crate::normalize::normalize_path;
+
+/// Names of the macros referencing assets by path.
+const MACROS: [&str; 2] = ["assets!", "asset_ids!"];
+
+/// Check the assets used with [`assets!`](crate::assets) and [`asset_ids!`](crate::asset_ids).
+///
+/// For use from a build script (`build.rs`). Scans the Rust files in `source_dir` and its
+/// subfolders for invocations of the macros, skipping comments, and checks each path given in the
+/// enums against the `root` of the `assets!` invocation, relative to the file holding it. Paths
+/// given to `asset_ids!`, or to `assets!` without a `root`, are checked against `asset_folder`:
+///
+/// - The path must match the case of the file exactly. A path with the wrong case works on Windows
+///   and macOS, but fails on case-sensitive filesystems. Reported according to `portability`.
+/// - The file must not be a Git LFS pointer file, left in place of the asset when it hasn't been
+///   pulled.
+///
+/// Paths that don't match any file at all are not reported. Emits `cargo:rerun-if-changed` for
+/// the scanned files and directories.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+/// # Errors
+///
+/// Fails with [`BuildError::LfsPointers`] if any of the assets are Git LFS pointer files, with
+/// [`BuildError::NotPortable`] if `portability` is [`Portability::Deny`] and the case of any paths
+/// doesn't match, or if reading the source files fails.
+pub fn check_asset_macros(
+    source_dir: &Path,
+    asset_folder: &Path,
+    portability: Portability,
+) -> Result<(), BuildError> {
+    let mut sources = vec![];
+    rust_files(source_dir, &mut sources)?;
+    let mut issues = vec![];
+    let mut files = vec![];
+    for source in sources {
+        println!("cargo:rerun-if-changed={}", source.display());
+        let code = fs::read_to_string(&source).map_err(BuildError::io(&source))?;
+        for assets in macro_asset_paths(&code) {
+            let folder = match &assets.root {
+                Some(root) => macro_folder(source.parent().unwrap_or(source_dir), root),
+                None => asset_folder.to_path_buf(),
+            };
+            for path in assets.paths {
+                let path = normalize_path(&path);
+                if portability != Portability::Allow {
+                    issues.extend(portability::check_case(&folder, &path));
+                }
+                let file = folder.join(&path);
+                if file.is_file() {
+                    println!("cargo:rerun-if-changed={}", file.display());
+                    files.push(file);
+                }
+            }
+        }
+    }
+    validate::check_lfs_pointers(files.iter().map(PathBuf::as_path))?;
+    portability.report(issues)
+}
+
+/// Collects the Rust files in `dir` and its subfolders.
+fn rust_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), BuildError> {
+    println!("cargo:rerun-if-changed={}", dir.display());
+    let mut entries = fs::read_dir(dir)
+        .and_then(|entries| {
+            entries
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(BuildError::io(dir))?;
+    entries.sort();
+    for path in entries {
+        if path.is_dir() {
+            rust_files(&path, files)?;
+        } else if matches!(path.extension(), Some(ext) if ext == "rs") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The asset paths given in the enum of a macro invocation.
+#[derive(Debug, PartialEq, Eq)]
+struct MacroAssets {
+    /// The `root` given to `assets!`, which the paths are relative to.
+    root: Option<String>,
+    paths: Vec<String>,
+}
+
+/// The asset paths given in the enums of the macro invocations in `code`, ignoring comments.
+fn macro_asset_paths(code: &str) -> Vec<MacroAssets> {
+    let code = strip_comments(code);
+    let mut found = vec![];
+    for name in MACROS {
+        for (start, _) in code.match_indices(name) {
+            let preceded_by_ident = matches!(
+                code[..start].chars().next_back(),
+                Some(c) if c == '_' || c.is_alphanumeric()
+            );
+            let rest = &code[start + name.len()..];
+            if let (false, Some(open)) = (preceded_by_ident, rest.find('{')) {
+                let (paths, end) = enum_paths(&rest[open + 1..]);
+                let root = match name {
+                    "assets!" => macro_root(&rest[open + 1 + end..]),
+                    _ => None,
+                };
+                found.push(MacroAssets { root, paths });
+            }
+        }
+    }
+    found
+}
+
+/// The folder `root` refers to, as given to `assets!` in a file in `dir`.
+fn macro_folder(dir: &Path, root: &str) -> PathBuf {
+    let mut folder = dir.to_path_buf();
+    for component in Path::new(root).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if folder.file_name().is_some() => {
+                folder.pop();
+            }
+            component => folder.push(component),
+        }
+    }
+    folder
+}
+
+/// The `root` given in the struct following the enum of `assets!`, if any.
+fn macro_root(rest: &str) -> Option<String> {
+    let body = &rest[rest.find('{')? + 1..];
+    let body = &body[..body.find('}')?];
+    let mut value = body
+        .trim_start()
+        .strip_prefix("root")?
+        .trim_start()
+        .strip_prefix('=')?
+        .trim_start()
+        .strip_prefix('"')?
+        .chars();
+    Some(string_literal(&mut value))
+}
+
+/// The string literals assigned to variants in `body`, up until the enum is closed, along with
+/// the length of the enum body including the closing brace.
+///
+/// Attributes are skipped, so e.g. `#[doc = "..."]` isn't mistaken for a variant. Comments must
+/// already have been removed.
+fn enum_paths(body: &str) -> (Vec<String>, usize) {
+    let mut paths = vec![];
+    let mut chars = body.chars();
+    let mut assigned = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '}' => break,
+            '=' => assigned = true,
+            '"' => {
+                let literal = string_literal(&mut chars);
+                if assigned {
+                    paths.push(literal);
+                }
+                assigned = false;
+            }
+            '#' => {
+                let mut depth = 0;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '[' => depth += 1,
+                        ']' if depth <= 1 => break,
+                        ']' => depth -= 1,
+                        '"' => {
+                            string_literal(&mut chars);
+                        }
+                        _ => {}
+                    }
+                }
+                assigned = false;
+            }
+            c if !c.is_whitespace() => assigned = false,
+            _ => {}
+        }
+    }
+    (paths, body.len() - chars.as_str().len())
+}
+
+/// `code` with the line and block comments replaced by spaces, keeping line breaks.
+///
+/// String, raw string and character literals are kept as they are, so e.g. `"//"` isn't mistaken
+/// for a comment.
+fn strip_comments(code: &str) -> String {
+    let mut stripped = String::with_capacity(code.len());
+    let mut chars = code.chars().peekable();
+    let mut word = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                stripped.push(' ');
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        stripped.push('\n');
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                stripped.push(' ');
+                let mut depth = 1;
+                while depth > 0 {
+                    match chars.next() {
+                        Some('*') if chars.peek() == Some(&'/') => {
+                            chars.next();
+                            depth -= 1;
+                        }
+                        Some('/') if chars.peek() == Some(&'*') => {
+                            chars.next();
+                            depth += 1;
+                        }
+                        Some('\n') => stripped.push('\n'),
+                        Some(_) => {}
+                        None => break,
+                    }
+                }
+            }
+            '"' => {
+                stripped.push(c);
+                while let Some(c) = chars.next() {
+                    stripped.push(c);
+                    match c {
+                        '\\' => stripped.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            'r' if (word.is_empty() || word == "b") && matches!(chars.peek(), Some('#' | '"')) => {
+                stripped.push(c);
+                let mut hashes = 0;
+                while chars.peek() == Some(&'#') {
+                    chars.next();
+                    stripped.push('#');
+                    hashes += 1;
+                }
+                if chars.peek() == Some(&'"') {
+                    stripped.push('"');
+                    chars.next();
+                    let closing = format!("\"{}", "#".repeat(hashes));
+                    let mut literal = String::new();
+                    for c in chars.by_ref() {
+                        literal.push(c);
+                        if literal.ends_with(&closing) {
+                            break;
+                        }
+                    }
+                    stripped.push_str(&literal);
+                }
+            }
+            '\'' => {
+                stripped.push(c);
+                let mut ahead = chars.clone();
+                let is_char = match ahead.next() {
+                    Some('\\') => true,
+                    Some(_) => ahead.next() == Some('\''),
+                    None => false,
+                };
+                if is_char {
+                    while let Some(c) = chars.next() {
+                        stripped.push(c);
+                        match c {
+                            '\\' => stripped.extend(chars.next()),
+                            '\'' => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            c => stripped.push(c),
+        }
+        if c == '_' || c.is_alphanumeric() {
+            word.push(c);
+        } else {
+            word.clear();
+        }
+    }
+    stripped
+}
+
+/// Reads a string literal, after the opening quote, up to and including the closing quote.
+fn string_literal(chars: &mut impl Iterator<Item = char>) -> String {
+    let mut literal = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => literal.extend(chars.next()),
+            c => literal.push(c),
+        }
+    }
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{check_asset_macros, macro_asset_paths, strip_comments, MacroAssets};
+    use crate::{
+        test_util::{test_dir, write_files},
+        BuildError, Portability,
+    };
+
+    #[test]
+    fn paths_from_macros() {
+        let code = r#"
+            bevy_embasset::assets!(
+                pub enum GameAssets {
+                    #[doc = "The icon = \"icon.png\""]
+                    Icon = "icon.png",
+                    // Old = "old.png",
+                    /* Older = "older.png", */
+                    Ship = "Textures/ship.png",
+                },
+                pub struct GameAssetsIo {
+                    root = "../assets/"
+                }
+            );
+            my_assets!(Other { Other = "other.png" });
+            asset_ids!(pub enum Ids { Music = "sounds/theme.ogg", });
+            /*
+            asset_ids!(pub enum Old { Music = "sounds/old.ogg", });
+            */
+            // assets!(pub enum Old { Icon = "old.png", }, pub struct OldIo { root = "../old/" });
+        "#;
+        assert_eq!(
+            macro_asset_paths(code),
+            [
+                MacroAssets {
+                    root: Some("../assets/".to_string()),
+                    paths: vec!["icon.png".to_string(), "Textures/ship.png".to_string()],
+                },
+                MacroAssets {
+                    root: None,
+                    paths: vec!["sounds/theme.ogg".to_string()],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_are_stripped() {
+        assert_eq!(strip_comments("a // b\nc"), "a  \nc");
+        assert_eq!(strip_comments("a /* b /* c */\n d */ e"), "a  \n e");
+        assert_eq!(strip_comments(r#"a = "// b", c"#), r#"a = "// b", c"#);
+        assert_eq!(
+            strip_comments(r##"a = r#"/* "b" */"#, c"##),
+            r##"a = r#"/* "b" */"#, c"##
+        );
+        assert_eq!(strip_comments("'\"' /* a */ 'b"), "'\"'   'b");
+    }
+
+    #[test]
+    fn check_macros() {
+        let dir = test_dir("check-macros");
+        write_files(
+            &dir,
+            &[
+                ("assets/Icon.png", "png"),
+                ("dlc/Level.ron", "ron"),
+                (
+                    "src/main.rs",
+                    r#"assets!(pub enum A { Icon = "icon.png", }, pub struct AIo { root = "../assets/" });"#,
+                ),
+                (
+                    "src/dlc/mod.rs",
+                    r#"assets!(pub enum D { Level = "Level.ron", }, pub struct DIo { root = "../../dlc/" });
+                    /* assets!(pub enum Old { Icon = "ICON.png", }, pub struct OldIo { root = "../../assets/" }); */"#,
+                ),
+            ],
+        );
+
+        let check =
+            |portability| check_asset_macros(&dir.join("src"), &dir.join("assets"), portability);
+        assert!(check(Portability::Warn).is_ok());
+        assert!(matches!(
+            check(Portability::Deny),
+            Err(BuildError::NotPortable(issues)) if issues.len() == 1
+        ));
+
+        fs::write(
+            dir.join("src/main.rs"),
+            r#"assets!(pub enum A { Icon = "Icon.png", }, pub struct AIo { root = "../assets/" });"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("assets/Icon.png"),
+            "version https://git-lfs.github.com/spec/v1\noid sha256:4d7a\nsize 3\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            check(Portability::Deny),
+            Err(BuildError::LfsPointers(pointers)) if pointers == [dir.join("assets/Icon.png")]
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use super::builder::EmbeddedAsset;
+
+/// Hex encoded SHA-256 hash of `data`.
+pub(super) fn sha256(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Creates the JSON manifest listing the embedded assets.
+///
+/// The assets are expected to be sorted by path, making the manifest stable between builds.
+pub(super) fn to_json(assets: &[EmbeddedAsset]) -> String {
+    let assets = assets
+        .iter()
+        .map(|asset| {
+            json!({
+                "path": asset.path,
+                "size": asset.size,
+                "sha256": asset.sha256,
+                "compression": format!("{:?}", asset.compression),
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut manifest = serde_json::to_string_pretty(&json!({ "assets": assets })).unwrap();
+    manifest.push('\n');
+    manifest
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::Compression;
+
+    #[test]
+    fn sha256_hex() {
+        assert_eq!(
+            sha256(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn manifest_json() {
+        let assets = [EmbeddedAsset {
+            path: "textures/ship.png".to_string(),
+            size: 3,
+            embedded_size: 3,
+            sha256: sha256(b"abc"),
+            compression: Compression::None,
+            blob: PathBuf::from("/assets/textures/ship.png"),
+            source: PathBuf::from("/assets/textures/ship.png"),
+            duplicate: false,
+        }];
+        let manifest: serde_json::Value = serde_json::from_str(&to_json(&assets)).unwrap();
+        assert_eq!(manifest["assets"][0]["path"], "textures/ship.png");
+        assert_eq!(manifest["assets"][0]["size"], 3);
+        assert_eq!(manifest["assets"][0]["sha256"], sha256(b"abc"));
+        assert_eq!(manifest["assets"][0]["compression"], "None");
+        assert_eq!(to_json(&assets), to_json(&assets));
+    }
+}
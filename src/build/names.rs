@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use super::BuildError;
+
+/// Derives an enum variant name from the path of an asset.
+///
+/// The extension is dropped, and the remaining path is split into words at every character
+/// that isn't alphanumeric, e.g. `textures/ship_red.png` becomes `TexturesShipRed`.
+///
+/// Returns `None` if no valid identifier could be derived.
+pub(super) fn variant_name(path: &str) -> Option<String> {
+    let without_extension = match path.rfind('.') {
+        Some(dot) if dot > 0 && !path[..dot].ends_with('/') => &path[..dot],
+        _ => path,
+    };
+    let mut name = String::new();
+    for word in without_extension
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            name.push(first.to_ascii_uppercase());
+            name.extend(chars);
+        }
+    }
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert_str(0, "Asset");
+    }
+    if name.is_empty() || name == "Self" {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// Derives an enum variant name for each of the asset paths.
+///
+/// # Errors
+///
+/// Fails if a name couldn't be derived for a path, or if several paths result in the same name.
+pub(super) fn variant_names<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+) -> Result<Vec<String>, BuildError> {
+    let mut used: HashMap<String, &str> = HashMap::new();
+    let mut names = vec![];
+    for path in paths {
+        let name = variant_name(path).ok_or_else(|| BuildError::InvalidName {
+            name: path.to_string(),
+            reason: "unable to derive an enum variant name from the asset path".to_string(),
+        })?;
+        if let Some(other) = used.insert(name.clone(), path) {
+            return Err(BuildError::InvalidName {
+                name: name.clone(),
+                reason: format!(
+                    "the assets {:?} and {:?} both result in this enum variant name",
+                    other, path
+                ),
+            });
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{variant_name, variant_names};
+
+    #[test]
+    fn derive_variant_names() {
+        assert_eq!(
+            variant_name("textures/ship_red.png").as_deref(),
+            Some("TexturesShipRed")
+        );
+        assert_eq!(
+            variant_name("sounds/bg-music.v2.ogg").as_deref(),
+            Some("SoundsBgMusicV2")
+        );
+        assert_eq!(variant_name(".keepme").as_deref(), Some("Keepme"));
+        assert_eq!(
+            variant_name("fonts/.hidden").as_deref(),
+            Some("FontsHidden")
+        );
+        assert_eq!(
+            variant_name("01_intro.ogg").as_deref(),
+            Some("Asset01Intro")
+        );
+        assert_eq!(variant_name("_.png"), None);
+        assert_eq!(variant_name("self.png"), None);
+    }
+
+    #[test]
+    fn detect_collisions() {
+        assert_eq!(
+            variant_names(["a.png", "b.png"]).unwrap(),
+            vec!["A".to_string(), "B".to_string()]
+        );
+        let err = variant_names(["ship_red.png", "ship-red.png"])
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("ship_red.png"));
+        assert!(err.contains("ship-red.png"));
+        assert!(err.contains("ShipRed"));
+    }
+}
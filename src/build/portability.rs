@@ -0,0 +1,243 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt, fs, iter,
+    path::Path,
+};
+
+use super::BuildError;
+
+/// Default limit on the length of asset paths, leaving room for the folder the assets are placed
+/// in below the 260 characters Windows allows by default.
+pub(super) const DEFAULT_MAX_PATH_LENGTH: usize = 200;
+
+/// Names reserved by Windows, regardless of extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Characters not allowed in file names on Windows.
+const RESERVED_CHARS: [char; 7] = ['<', '>', ':', '"', '|', '?', '*'];
+
+/// What to do about asset paths that won't work on all platforms.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Portability {
+    /// Don't check the asset paths.
+    Allow,
+    /// Emit a warning for each problem found.
+    #[default]
+    Warn,
+    /// Fail the build with [`BuildError::NotPortable`].
+    Deny,
+}
+
+/// An asset path that won't work on all platforms.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortabilityIssue {
+    /// The asset path, or the part of it with the problem.
+    pub path: String,
+    /// What the problem is.
+    pub problem: String,
+}
+
+impl fmt::Display for PortabilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.path, self.problem)
+    }
+}
+
+impl Portability {
+    /// Warns about or fails on the `issues`, as configured.
+    pub(super) fn report(self, issues: Vec<PortabilityIssue>) -> Result<(), BuildError> {
+        match self {
+            Portability::Deny if !issues.is_empty() => Err(BuildError::NotPortable(issues)),
+            Portability::Warn => {
+                for issue in issues {
+                    println!("cargo:warning={}", issue);
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Checks the normalized asset `paths` for problems on case-insensitive filesystems and Windows.
+///
+/// Each directory is only checked once, no matter how many assets it holds.
+pub(super) fn check_paths<'a>(
+    paths: impl IntoIterator<Item = &'a str>,
+    max_path_length: usize,
+) -> Vec<PortabilityIssue> {
+    let mut issues = vec![];
+    let mut folded: HashMap<String, &str> = HashMap::new();
+    let mut collisions = HashSet::new();
+    for path in paths {
+        let ends = path
+            .match_indices('/')
+            .map(|(end, _)| end)
+            .chain(iter::once(path.len()));
+        for end in ends {
+            let prefix = &path[..end];
+            match folded.entry(prefix.to_lowercase()) {
+                Entry::Occupied(entry) => {
+                    let other = *entry.get();
+                    if other != prefix && collisions.insert(prefix) {
+                        issues.push(issue(
+                            prefix,
+                            format!("differs only in case from {:?}", other),
+                        ));
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(prefix);
+                    let name = prefix.rsplit('/').next().unwrap_or(prefix);
+                    if let Some(problem) = check_name(name) {
+                        issues.push(issue(prefix, problem));
+                    }
+                }
+            }
+        }
+        let length = path.encode_utf16().count();
+        if length > max_path_length {
+            issues.push(issue(
+                path,
+                format!(
+                    "is {} characters long, exceeding the limit of {}",
+                    length, max_path_length
+                ),
+            ));
+        }
+    }
+    issues
+}
+
+/// Checks that the normalized `path` matches the case of a file in `asset_folder` exactly.
+///
+/// Returns `None` if it does, or if no file matches the path at all.
+pub(super) fn check_case(asset_folder: &Path, path: &str) -> Option<PortabilityIssue> {
+    let mut actual = vec![];
+    let mut dir = asset_folder.to_path_buf();
+    for component in path.split('/') {
+        if has_entry(&dir, component) {
+            actual.push(component.to_string());
+            dir.push(component);
+            continue;
+        }
+        let folded = component.to_lowercase();
+        let found = fs::read_dir(&dir)
+            .ok()?
+            .flatten()
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .find(|name| name.to_lowercase() == folded)?;
+        dir.push(&found);
+        actual.push(found);
+    }
+    let actual = actual.join("/");
+    (actual != path).then(|| issue(path, format!("does not match the case of {:?}", actual)))
+}
+
+/// Checks if `dir` holds an entry named exactly `name`, which `exists` doesn't tell on
+/// case-insensitive filesystems.
+fn has_entry(dir: &Path, name: &str) -> bool {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().any(|entry| entry.file_name() == name))
+        .unwrap_or(false)
+}
+
+/// Checks a single file or directory name for problems on Windows.
+fn check_name(name: &str) -> Option<String> {
+    let stem = name.split('.').next().unwrap_or(name);
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.eq_ignore_ascii_case(reserved))
+    {
+        return Some(format!(
+            "uses the name {:?}, which is reserved on Windows",
+            stem
+        ));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| RESERVED_CHARS.contains(c) || c.is_control())
+    {
+        return Some(format!("contains {:?}, which is not allowed on Windows", c));
+    }
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Some("ends with a dot or space, which Windows removes".to_string());
+    }
+    None
+}
+
+fn issue(path: &str, problem: String) -> PortabilityIssue {
+    PortabilityIssue {
+        path: path.to_string(),
+        problem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_case, check_paths};
+    use crate::test_util::{test_dir, write_files};
+
+    fn problems(paths: &[&str], max_path_length: usize) -> Vec<String> {
+        check_paths(paths.iter().copied(), max_path_length)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn portable_paths() {
+        assert!(problems(&["conf.d/a.png", "ui/consoles.png", "a.b.png"], 200).is_empty());
+        assert_eq!(
+            problems(
+                &[
+                    "Ship.png",
+                    "aux.png",
+                    "maps/level:1.ron",
+                    "ship.png",
+                    "sounds./a.ogg",
+                    "sounds./b.ogg",
+                    "textures/a.png",
+                    "Textures/b.png",
+                ],
+                14
+            ),
+            [
+                r#""aux.png" uses the name "aux", which is reserved on Windows"#,
+                r#""maps/level:1.ron" contains ':', which is not allowed on Windows"#,
+                r#""maps/level:1.ron" is 16 characters long, exceeding the limit of 14"#,
+                r#""ship.png" differs only in case from "Ship.png""#,
+                r#""sounds." ends with a dot or space, which Windows removes"#,
+                r#""Textures" differs only in case from "textures""#,
+            ]
+        );
+    }
+
+    #[test]
+    fn case_on_disk() {
+        let asset_folder = test_dir("case-on-disk");
+        write_files(&asset_folder, &[("Textures/Ship.png", "png")]);
+
+        assert!(check_case(&asset_folder, "Textures/Ship.png").is_none());
+        assert!(check_case(&asset_folder, "textures/missing.png").is_none());
+        assert_eq!(
+            check_case(&asset_folder, "textures/ship.png")
+                .unwrap()
+                .to_string(),
+            r#""textures/ship.png" does not match the case of "Textures/Ship.png""#
+        );
+    }
+}
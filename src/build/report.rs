@@ -0,0 +1,289 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    path::Path,
+};
+
+use super::builder::EmbeddedAsset;
+
+/// Number of assets listed as the largest in the report.
+const LARGEST_ASSETS: usize = 20;
+
+/// A size budget that was exceeded.
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BudgetViolation {
+    /// The embedded assets are larger than allowed in total.
+    Total {
+        /// Total size of the embedded assets, in bytes.
+        size: u64,
+        /// The budget, in bytes.
+        budget: u64,
+    },
+    /// An embedded asset is larger than allowed.
+    Asset {
+        /// Path of the asset.
+        path: String,
+        /// Size of the asset, in bytes.
+        size: u64,
+        /// The budget, in bytes.
+        budget: u64,
+    },
+}
+
+impl fmt::Display for BudgetViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetViolation::Total { size, budget } => write!(
+                f,
+                "embedded assets take up {}, exceeding the budget of {}",
+                format_size(*size),
+                format_size(*budget)
+            ),
+            BudgetViolation::Asset { path, size, budget } => write!(
+                f,
+                "{:?} takes up {}, exceeding the budget of {} per asset",
+                path,
+                format_size(*size),
+                format_size(*budget)
+            ),
+        }
+    }
+}
+
+/// Checks the embedded size of the assets against the budgets.
+pub(super) fn check_budgets(
+    assets: &[EmbeddedAsset],
+    max_total_size: Option<u64>,
+    max_asset_size: Option<u64>,
+) -> Vec<BudgetViolation> {
+    let mut violations = vec![];
+    if let Some(budget) = max_total_size {
+        let size = embedded_size(assets);
+        if size > budget {
+            violations.push(BudgetViolation::Total { size, budget });
+        }
+    }
+    if let Some(budget) = max_asset_size {
+        violations.extend(
+            assets
+                .iter()
+                .filter(|asset| asset.embedded_size > budget)
+                .map(|asset| BudgetViolation::Asset {
+                    path: asset.path.clone(),
+                    size: asset.embedded_size,
+                    budget,
+                }),
+        );
+    }
+    violations
+}
+
+/// Creates a human readable report of the space taken up by the embedded assets.
+///
+/// Identical assets share their embedded data, which is listed once with the paths of all of them,
+/// and only counted in the folder and extension of the first.
+pub(super) fn to_text(assets: &[EmbeddedAsset]) -> String {
+    let uncompressed: u64 = assets.iter().map(|asset| asset.size).sum();
+    let mut report = format!(
+        "Embedded assets: {} files, {} ({} uncompressed)\n",
+        assets.len(),
+        format_size(embedded_size(assets)),
+        format_size(uncompressed)
+    );
+    let duplicates = assets
+        .iter()
+        .filter(|asset| asset.duplicate)
+        .collect::<Vec<_>>();
+    if !duplicates.is_empty() {
+        report.push_str(&format!(
+            "Duplicates: {} files sharing the content of another asset, saving {}\n",
+            duplicates.len(),
+            format_size(duplicates.iter().map(|asset| asset.embedded_size).sum())
+        ));
+    }
+
+    // The embedded data, with the paths of the assets sharing it, in order of first use.
+    let mut largest: Vec<(u64, Vec<&str>)> = vec![];
+    let mut blobs: HashMap<&Path, usize> = HashMap::new();
+    for asset in assets {
+        match blobs.get(asset.blob.as_path()) {
+            Some(&i) => largest[i].1.push(&asset.path),
+            None => {
+                blobs.insert(&asset.blob, largest.len());
+                largest.push((asset.embedded_size, vec![&asset.path]));
+            }
+        }
+    }
+    largest.sort_by_key(|(size, _)| std::cmp::Reverse(*size));
+    report.push_str("\nLargest assets:\n");
+    for (size, paths) in largest.iter().take(LARGEST_ASSETS) {
+        report.push_str(&format!(
+            "{:>12}  {}\n",
+            format_size(*size),
+            paths.join(", ")
+        ));
+    }
+
+    let mut folders: BTreeMap<String, u64> = BTreeMap::new();
+    let mut extensions: BTreeMap<String, u64> = BTreeMap::new();
+    for asset in assets.iter().filter(|asset| !asset.duplicate) {
+        let folder = asset
+            .path
+            .rsplit_once('/')
+            .map_or(".", |(folder, _)| folder);
+        *folders.entry(folder.to_string()).or_default() += asset.embedded_size;
+        let extension = Path::new(&asset.path).extension().map_or_else(
+            || "(none)".to_string(),
+            |ext| ext.to_string_lossy().to_lowercase(),
+        );
+        *extensions.entry(extension).or_default() += asset.embedded_size;
+    }
+    push_sizes(&mut report, "folder", folders);
+    push_sizes(&mut report, "extension", extensions);
+    report
+}
+
+/// Adds a section to the report, listing the sizes largest first.
+fn push_sizes(report: &mut String, title: &str, sizes: BTreeMap<String, u64>) {
+    let mut sizes = sizes.into_iter().collect::<Vec<_>>();
+    sizes.sort_by(|(a_name, a), (b_name, b)| b.cmp(a).then(a_name.cmp(b_name)));
+    report.push_str(&format!("\nBy {}:\n", title));
+    for (name, size) in sizes {
+        report.push_str(&format!("{:>12}  {}\n", format_size(size), name));
+    }
+}
+
+/// Total size of the embedded assets, counting identical assets once.
+fn embedded_size(assets: &[EmbeddedAsset]) -> u64 {
+    assets
+        .iter()
+        .filter(|asset| !asset.duplicate)
+        .map(|asset| asset.embedded_size)
+        .sum()
+}
+
+/// Formats a size in bytes for humans.
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::Compression;
+
+    fn asset(path: &str, size: u64, embedded_size: u64) -> EmbeddedAsset {
+        EmbeddedAsset {
+            path: path.to_string(),
+            size,
+            embedded_size,
+            sha256: String::new(),
+            compression: Compression::None,
+            blob: PathBuf::from(path),
+            source: PathBuf::from(path),
+            duplicate: false,
+        }
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn budgets() {
+        let assets = [asset("a.png", 4000, 3000), asset("ui/b.png", 500, 500)];
+        assert!(check_budgets(&assets, Some(3500), Some(3000)).is_empty());
+        assert_eq!(
+            check_budgets(&assets, Some(3000), Some(1000)),
+            vec![
+                BudgetViolation::Total {
+                    size: 3500,
+                    budget: 3000
+                },
+                BudgetViolation::Asset {
+                    path: "a.png".to_string(),
+                    size: 3000,
+                    budget: 1000
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn report() {
+        let assets = [
+            asset("a.png", 4000, 3000),
+            asset("ui/b.png", 500, 500),
+            asset("ui/c.ron", 2048, 2048),
+        ];
+        assert_eq!(
+            to_text(&assets),
+            "Embedded assets: 3 files, 5.4 KiB (6.4 KiB uncompressed)
+
+Largest assets:
+     2.9 KiB  a.png
+     2.0 KiB  ui/c.ron
+       500 B  ui/b.png
+
+By folder:
+     2.9 KiB  .
+     2.5 KiB  ui
+
+By extension:
+     3.4 KiB  png
+     2.0 KiB  ron
+"
+        );
+    }
+
+    #[test]
+    fn report_duplicates() {
+        let mut duplicate = asset("ui/b.png", 2048, 2048);
+        duplicate.blob = PathBuf::from("a.png");
+        duplicate.duplicate = true;
+        let assets = [
+            asset("a.png", 2048, 2048),
+            asset("c.ron", 100, 100),
+            duplicate,
+        ];
+        assert_eq!(
+            to_text(&assets),
+            "Embedded assets: 3 files, 2.1 KiB (4.1 KiB uncompressed)
+Duplicates: 1 files sharing the content of another asset, saving 2.0 KiB
+
+Largest assets:
+     2.0 KiB  a.png, ui/b.png
+       100 B  c.ron
+
+By folder:
+     2.1 KiB  .
+
+By extension:
+     2.0 KiB  png
+       100 B  ron
+"
+        );
+        assert!(check_budgets(&assets, Some(2148), None).is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashSet,
+    env,
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::UNIX_EPOCH,
+};
+
+use super::{manifest, BuildError};
+use crate::normalize::normalize_path;
+
+/// Error returned by a transform.
+pub type TransformError = Box<dyn Error + Send + Sync>;
+
+/// The output of a transform added using
+/// [`IncludeOptions::transform`](super::IncludeOptions::transform).
+///
+/// # Requires
+///
+/// Feature: `build`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transformed {
+    /// The transformed content, which is embedded instead of the original.
+    pub data: Vec<u8>,
+    /// New path for the asset, or `None` to keep the original path.
+    pub path: Option<String>,
+}
+
+impl Transformed {
+    /// Transformed content, keeping the original path.
+    pub fn new(data: Vec<u8>) -> Self {
+        Transformed { data, path: None }
+    }
+
+    /// Embed the transformed content using `path`, e.g. when converting `bg.wav` to `bg.ogg`.
+    #[must_use]
+    pub fn renamed(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+impl From<Vec<u8>> for Transformed {
+    fn from(data: Vec<u8>) -> Self {
+        Transformed::new(data)
+    }
+}
+
+type TransformFn = dyn Fn(&str, &[u8]) -> Result<Transformed, TransformError> + Send + Sync;
+
+/// A transform, as added to [`IncludeOptions`](super::IncludeOptions).
+#[derive(Clone)]
+pub(super) struct Transform(Arc<TransformFn>);
+
+impl Transform {
+    pub(super) fn new<F>(transform: F) -> Self
+    where
+        F: Fn(&str, &[u8]) -> Result<Transformed, TransformError> + Send + Sync + 'static,
+    {
+        Transform(Arc::new(transform))
+    }
+
+    /// Transforms `source`, embedded as `path`, unless a cached result is found in `cache_dir`.
+    ///
+    /// Returns the path to embed the asset as, along with the file holding the transformed
+    /// content.
+    pub(super) fn apply(
+        &self,
+        cache_dir: &Path,
+        path: &str,
+        source: &Path,
+    ) -> Result<(String, PathBuf), BuildError> {
+        let data = fs::read(source).map_err(BuildError::io(source))?;
+        let key = cache_key(path, &data);
+        let output = cache_dir.join(&key);
+        let renamed = cache_dir.join(format!("{}.path", key));
+        if output.is_file() {
+            let path = match fs::read_to_string(&renamed) {
+                Ok(renamed) => renamed,
+                Err(_) => path.to_string(),
+            };
+            return Ok((path, output));
+        }
+
+        let transformed = (self.0)(path, &data).map_err(|err| BuildError::Transform {
+            path: source.to_path_buf(),
+            reason: err.to_string(),
+        })?;
+        fs::create_dir_all(cache_dir).map_err(BuildError::io(cache_dir))?;
+        let path = match transformed.path {
+            Some(path) => {
+                let path = normalize_path(&path);
+                fs::write(&renamed, &path).map_err(BuildError::io(&renamed))?;
+                path
+            }
+            None => path.to_string(),
+        };
+        fs::write(&output, transformed.data).map_err(BuildError::io(&output))?;
+        Ok((path, output))
+    }
+}
+
+/// Removes the cached transforms in `cache_dir` other than those holding one of `used`.
+///
+/// The cache is keyed on the modification time of the build script, so without this every change
+/// to the build script would leave a full set of stale outputs behind.
+pub(super) fn remove_unused<'a>(
+    cache_dir: &Path,
+    used: impl IntoIterator<Item = &'a Path>,
+) -> Result<(), BuildError> {
+    let used = used
+        .into_iter()
+        .filter_map(|file| file.strip_prefix(cache_dir).ok())
+        .filter_map(|key| key.to_str())
+        .collect::<HashSet<_>>();
+    let entries = match fs::read_dir(cache_dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(BuildError::io(cache_dir)(err)),
+    };
+    for entry in entries {
+        let path = entry.map_err(BuildError::io(cache_dir))?.path();
+        let key = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.strip_suffix(".path").unwrap_or(name));
+        if !matches!(key, Some(key) if used.contains(key)) {
+            fs::remove_file(&path).map_err(BuildError::io(&path))?;
+        }
+    }
+    Ok(())
+}
+
+impl fmt::Debug for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Transform")
+    }
+}
+
+/// Key identifying the transform of `data`, embedded as `path`.
+///
+/// As the transform itself can't be hashed, the key includes the modification time of the build
+/// script, making changes to the build script invalidate the cache.
+fn cache_key(path: &str, data: &[u8]) -> String {
+    let build_script = env::current_exe()
+        .and_then(|exe| exe.metadata())
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_nanos())
+        .unwrap_or_default();
+    let mut input = format!("{}\0{}\0", build_script, path).into_bytes();
+    input.extend_from_slice(data);
+    manifest::sha256(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        sync::atomic::{AtomicUsize, Ordering},
+        sync::Arc,
+    };
+
+    use super::{remove_unused, Transform, Transformed};
+    use crate::{test_util::test_dir, BuildError};
+
+    #[test]
+    fn transform_is_cached() {
+        let dir = test_dir("transform-is-cached");
+        let source = dir.join("bg.wav");
+        fs::write(&source, "wav").unwrap();
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let transform = Transform::new({
+            let calls = calls.clone();
+            move |path, data| {
+                calls.fetch_add(1, Ordering::SeqCst);
+                let mut data = data.to_vec();
+                data.reverse();
+                Ok(Transformed::new(data).renamed(path.replace(".wav", ".ogg")))
+            }
+        });
+        let cache_dir = dir.join("cache");
+
+        for _ in 0..2 {
+            let (path, output) = transform
+                .apply(&cache_dir, "sounds/bg.wav", &source)
+                .unwrap();
+            assert_eq!(path, "sounds/bg.ogg");
+            assert_eq!(fs::read(output).unwrap(), b"vaw");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        fs::write(&source, "wave").unwrap();
+        let (_, output) = transform
+            .apply(&cache_dir, "sounds/bg.wav", &source)
+            .unwrap();
+        assert_eq!(fs::read(output).unwrap(), b"evaw");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn unused_transforms_are_removed() {
+        let dir = test_dir("unused-transforms");
+        fs::write(dir.join("bg.wav"), "wav").unwrap();
+        fs::write(dir.join("fg.wav"), "wav").unwrap();
+        let transform =
+            Transform::new(|path, data| Ok(Transformed::new(data.to_vec()).renamed(path)));
+        let cache_dir = dir.join("cache");
+        let (_, bg) = transform
+            .apply(&cache_dir, "bg.wav", &dir.join("bg.wav"))
+            .unwrap();
+        let (_, fg) = transform
+            .apply(&cache_dir, "fg.wav", &dir.join("fg.wav"))
+            .unwrap();
+        assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 4);
+
+        remove_unused(&cache_dir, [bg.as_path(), dir.join("bg.wav").as_path()]).unwrap();
+        let mut left = fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect::<Vec<_>>();
+        left.sort();
+        assert_eq!(left, [bg.clone(), bg.with_extension("path")]);
+        assert!(!fg.exists());
+
+        remove_unused(&dir.join("missing"), []).unwrap();
+    }
+
+    #[test]
+    fn failing_transform() {
+        let dir = test_dir("failing-transform");
+        let source = dir.join("level.json");
+        fs::write(&source, "{").unwrap();
+
+        let transform = Transform::new(|_, _| Err("unexpected end of input".into()));
+        assert!(matches!(
+            transform.apply(&dir.join("cache"), "level.json", &source),
+            Err(BuildError::Transform { path, reason })
+                if path == source && reason == "unexpected end of input"
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::BTreeSet;
+
+use crate::normalize::normalize_path;
+
+/// Picks the files used according to `recorded`, the content of a file written by
+/// [`EmbassetIo::record_usage`](crate::EmbassetIo::record_usage).
+///
+/// `files` are the files included from an asset folder mounted at `mount`, relative to the folder.
+/// Recorded directories include all files below them, and recorded paths outside of the mount,
+/// or not matching any of the files, are ignored as they may have been served by something else.
+///
+/// Returns the used files, and the files that weren't used, both sorted.
+pub(super) fn used_files(
+    files: &[String],
+    mount: &str,
+    recorded: &str,
+) -> (Vec<String>, Vec<String>) {
+    let existing = files.iter().map(String::as_str).collect::<BTreeSet<_>>();
+    let mut used = BTreeSet::new();
+    for line in recorded
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let (path, is_directory) = match line.strip_suffix('/') {
+            Some(path) => (normalize_path(path), true),
+            None => (normalize_path(line), false),
+        };
+        let path = if mount.is_empty() {
+            path.as_str()
+        } else if path == mount {
+            ""
+        } else {
+            match path
+                .strip_prefix(mount)
+                .and_then(|path| path.strip_prefix('/'))
+            {
+                Some(path) => path,
+                None => continue,
+            }
+        };
+        if is_directory {
+            let prefix = format!("{}/", path);
+            used.extend(
+                existing
+                    .iter()
+                    .copied()
+                    .filter(|file| path.is_empty() || file.starts_with(&prefix)),
+            );
+        } else if let Some(file) = existing.get(path) {
+            used.insert(*file);
+        }
+    }
+    let unused = existing
+        .difference(&used)
+        .map(|file| file.to_string())
+        .collect();
+    (used.into_iter().map(str::to_string).collect(), unused)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::used_files;
+
+    #[test]
+    fn recorded_usage() {
+        let files = [
+            "a.png",
+            "levels/1.ron",
+            "levels/2.ron",
+            "ui/b.png",
+            "unused.png",
+        ]
+        .iter()
+        .map(|file| file.to_string())
+        .collect::<Vec<_>>();
+        let recorded = "a.png\nlevels/\nmissing.png\nui\\\\b.png\n";
+        let (used, unused) = used_files(&files, "", recorded);
+        assert_eq!(used, ["a.png", "levels/1.ron", "levels/2.ron", "ui/b.png"]);
+        assert_eq!(unused, ["unused.png"]);
+
+        let recorded = "a.png\ngame/levels/1.ron\ngame/ui/\n";
+        let (used, _) = used_files(&files, "game", recorded);
+        assert_eq!(used, ["levels/1.ron", "ui/b.png"]);
+        let (used, unused) = used_files(&files, "game", "/\ngame/\n");
+        assert_eq!(used.len(), 5);
+        assert!(unused.is_empty());
+    }
+}
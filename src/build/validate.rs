@@ -0,0 +1,178 @@
+use std::{error::Error, fmt, fs, path::Path, str, sync::Arc};
+
+use super::BuildError;
+
+/// First line of a Git LFS pointer file.
+const LFS_POINTER_VERSION: &[u8] = b"version https://git-lfs.github.com/spec/v1\n";
+
+/// Git LFS pointer files are always smaller than this.
+const LFS_POINTER_MAX_SIZE: u64 = 1024;
+
+type ValidatorFn = dyn Fn(&str, &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> + Send + Sync;
+
+/// A validator, as added to [`IncludeOptions`](super::IncludeOptions).
+#[derive(Clone)]
+pub(super) struct Validator(Arc<ValidatorFn>);
+
+impl Validator {
+    pub(super) fn new<F>(validator: F) -> Self
+    where
+        F: Fn(&str, &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> + Send + Sync + 'static,
+    {
+        Validator(Arc::new(validator))
+    }
+
+    /// Validates `data`, embedded as `path`, returning the reason if it is invalid.
+    pub(super) fn validate(&self, path: &str, data: &[u8]) -> Result<(), String> {
+        (self.0)(path, data).map_err(|err| err.to_string())
+    }
+}
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Validator")
+    }
+}
+
+/// Validates `data` if `extension` (lowercase) is a well-known format, returning the reason if it
+/// is invalid.
+pub(super) fn validate_format(extension: &str, data: &[u8]) -> Result<(), String> {
+    let header = |valid: bool, format: &str| {
+        if valid {
+            Ok(())
+        } else {
+            Err(format!("not a valid {} file", format))
+        }
+    };
+    match extension {
+        "png" => header(
+            data.starts_with(b"\x89PNG\r\n\x1a\n") && data.get(12..16) == Some(b"IHDR"),
+            "PNG",
+        ),
+        "jpg" | "jpeg" => header(data.starts_with(&[0xff, 0xd8, 0xff]), "JPEG"),
+        "wav" => header(
+            data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WAVE"),
+            "WAV",
+        ),
+        "ogg" => header(data.starts_with(b"OggS"), "Ogg"),
+        "json" => serde_json::from_str::<serde_json::Value>(utf8(data)?)
+            .map(drop)
+            .map_err(|err| err.to_string()),
+        "ron" => ron::from_str::<ron::Value>(utf8(data)?)
+            .map(drop)
+            .map_err(|err| err.to_string()),
+        "toml" => utf8(data)?
+            .parse::<toml::Value>()
+            .map(drop)
+            .map_err(|err| err.to_string()),
+        _ => Ok(()),
+    }
+}
+
+/// Checks if `data` is a Git LFS pointer file, left in place of the actual content when the
+/// content hasn't been pulled.
+pub(super) fn is_lfs_pointer(data: &[u8]) -> bool {
+    data.len() < LFS_POINTER_MAX_SIZE as usize
+        && data.starts_with(LFS_POINTER_VERSION)
+        && str::from_utf8(data)
+            .map(|text| text.contains("\noid sha256:") && text.contains("\nsize "))
+            .unwrap_or(false)
+}
+
+/// Fails with [`BuildError::LfsPointers`], naming all of the `files` that are Git LFS pointers.
+///
+/// Only small files are read, so this is cheap to run before anything else.
+pub(super) fn check_lfs_pointers<'a>(
+    files: impl IntoIterator<Item = &'a Path>,
+) -> Result<(), BuildError> {
+    let mut pointers = vec![];
+    for file in files {
+        let size = fs::metadata(file).map_err(BuildError::io(file))?.len();
+        if size < LFS_POINTER_MAX_SIZE
+            && is_lfs_pointer(&fs::read(file).map_err(BuildError::io(file))?)
+        {
+            pointers.push(file.to_path_buf());
+        }
+    }
+    if pointers.is_empty() {
+        Ok(())
+    } else {
+        Err(BuildError::LfsPointers(pointers))
+    }
+}
+
+fn utf8(data: &[u8]) -> Result<&str, String> {
+    str::from_utf8(data).map_err(|err| format!("not valid UTF-8: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{check_lfs_pointers, is_lfs_pointer, validate_format, Validator};
+    use crate::{test_util::test_dir, BuildError};
+
+    const POINTER: &[u8] = b"version https://git-lfs.github.com/spec/v1
+oid sha256:4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393
+size 12345
+";
+
+    #[test]
+    fn well_known_formats() {
+        assert!(validate_format("png", b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR").is_ok());
+        assert!(validate_format("png", b"GIF89a").is_err());
+        assert!(validate_format("jpg", &[0xff, 0xd8, 0xff, 0xe0]).is_ok());
+        assert!(validate_format("jpeg", b"\x89PNG").is_err());
+        assert!(validate_format("wav", b"RIFF\0\0\0\0WAVEfmt ").is_ok());
+        assert!(validate_format("wav", b"RIFF\0\0\0\0AVI ").is_err());
+        assert!(validate_format("ogg", b"OggS\0\x02").is_ok());
+        assert!(validate_format("ogg", b"fLaC").is_err());
+        assert!(validate_format("json", br#"{"level": [1, 2]}"#).is_ok());
+        assert!(validate_format("json", br#"{"level": [1, 2}"#).is_err());
+        assert!(validate_format("ron", b"Level(size: (1, 2), name: \"first\")").is_ok());
+        assert!(validate_format("ron", b"Level(size: (1, 2)").is_err());
+        assert!(validate_format("toml", b"[level]\nsize = 2\n").is_ok());
+        assert!(validate_format("toml", b"[level\n").is_err());
+        assert!(validate_format("txt", b"anything").is_ok());
+        assert_eq!(
+            validate_format("png", b"GIF89a").unwrap_err(),
+            "not a valid PNG file"
+        );
+        assert_eq!(
+            validate_format("json", b"\xff").unwrap_err(),
+            "not valid UTF-8: invalid utf-8 sequence of 1 bytes from index 0"
+        );
+    }
+
+    #[test]
+    fn custom_validator() {
+        let validator = Validator::new(|_, data| {
+            if data.is_empty() {
+                Err("empty".into())
+            } else {
+                Ok(())
+            }
+        });
+        assert!(validator.validate("level.dat", b"data").is_ok());
+        assert_eq!(validator.validate("level.dat", b"").unwrap_err(), "empty");
+    }
+
+    #[test]
+    fn lfs_pointers() {
+        assert!(is_lfs_pointer(POINTER));
+        assert!(!is_lfs_pointer(
+            b"version https://git-lfs.github.com/spec/v1\n"
+        ));
+        assert!(!is_lfs_pointer(b"\x89PNG\r\n\x1a\n"));
+
+        let dir = test_dir("lfs-pointers");
+        let (texture, pointer) = (dir.join("texture.png"), dir.join("pointer.png"));
+        fs::write(&texture, b"\x89PNG\r\n\x1a\n").unwrap();
+        fs::write(&pointer, POINTER).unwrap();
+        assert!(check_lfs_pointers([texture.as_path()]).is_ok());
+        assert!(matches!(
+            check_lfs_pointers([texture.as_path(), pointer.as_path()]),
+            Err(BuildError::LfsPointers(pointers)) if pointers == [pointer]
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+use std::io;
+
+/// Compression applied to an embedded asset.
+///
+/// Assets are always handed to Bevy decompressed - the compression only affects how the asset
+/// is stored inside the binary.
+///
+/// Apart from [`Compression::None`], the variants are only available when the feature of the
+/// same name is enabled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// The asset is stored as is.
+    #[default]
+    None,
+    /// The asset is stored as a raw deflate stream.
+    ///
+    /// # Requires
+    ///
+    /// Feature: `deflate`
+    #[cfg(feature = "deflate")]
+    Deflate,
+    /// The asset is stored as a zstd frame.
+    ///
+    /// # Requires
+    ///
+    /// Feature: `zstd`
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// Decompress `data`, which must have been compressed using `self`.
+    pub(crate) fn decompress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => {
+                use io::Read;
+                let mut decompressed = Vec::with_capacity(data.len() * 2);
+                flate2::read::DeflateDecoder::new(data).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::stream::decode_all(data),
+        }
+    }
+
+    /// Identifier of the compression, as stored in an [`AssetPack`](crate::AssetPack).
+    #[cfg(feature = "build")]
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => 1,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => 2,
+        }
+    }
+
+    /// The compression identified by `id`, if known and enabled.
+    pub(crate) fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Compression::None),
+            #[cfg(feature = "deflate")]
+            1 => Some(Compression::Deflate),
+            #[cfg(feature = "zstd")]
+            2 => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Compress `data` using `self`.
+    #[cfg(feature = "build")]
+    pub(crate) fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => {
+                use io::Write;
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::best());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::stream::encode_all(data, 19),
+        }
+    }
+
+    /// File extension used for blobs written by the build script.
+    #[cfg(feature = "build")]
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Compression::None => "raw",
+            #[cfg(feature = "deflate")]
+            Compression::Deflate => "deflate",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zst",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Compression;
+
+    #[test]
+    fn none_is_identity() {
+        assert_eq!(Compression::None.decompress(&[1, 2, 3]).unwrap(), [1, 2, 3]);
+    }
+
+    #[cfg(all(feature = "build", feature = "deflate"))]
+    #[test]
+    fn deflate_roundtrip() {
+        let data = b"deflate deflate deflate deflate deflate".to_vec();
+        let compressed = Compression::Deflate.compress(&data).unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(Compression::Deflate.decompress(&compressed).unwrap(), data);
+    }
+
+    #[cfg(all(feature = "build", feature = "zstd"))]
+    #[test]
+    fn zstd_roundtrip() {
+        let data = b"zstd zstd zstd zstd zstd zstd zstd zstd zstd zstd".to_vec();
+        let compressed = Compression::Zstd.compress(&data).unwrap();
+        assert!(compressed.len() < data.len());
+        assert_eq!(Compression::Zstd.decompress(&compressed).unwrap(), data);
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use bevy::{
+    asset::{AssetIo, AssetIoError, BoxedFuture},
+    utils::HashMap,
+};
+
+use crate::normalize::normalize;
+
+/// [`AssetIo`] serving the files in a directory anywhere on disk, e.g. a folder of mods in the
+/// user's data directory.
+///
+/// Paths are relative to the root directory, and paths leading outside of it are not found. Use
+/// it for a prefix, or as a layer of [`EmbassetIo`](crate::EmbassetIo):
+///
+/// ```ignore
+/// App::new().add_embasset_plugin(|io| {
+///     io.add_handler(AssetIoAlternative::new(
+///         "user://",
+///         DirectoryAssetIo::new(data_dir.join("ourgame/user"), false),
+///         false,
+///     ))
+///     .add_layer("mods", 10, DirectoryAssetIo::new(data_dir.join("ourgame/mods"), true));
+/// });
+/// ```
+///
+/// When watching for changes, the modification times of the loaded files are checked regularly by
+/// [`EmbassetPlugin`](crate::EmbassetPlugin), which sends an [`AssetChanged`] event for each
+/// changed asset. This works when the `DirectoryAssetIo` is added directly to the `EmbassetIo`
+/// used by the [`AssetServer`](bevy::asset::AssetServer), as a handler or a layer.
+#[derive(Debug)]
+pub struct DirectoryAssetIo {
+    root: PathBuf,
+    /// The last modification time of each watched file, if watching for changes.
+    watched: Mutex<Option<HashMap<PathBuf, Option<SystemTime>>>>,
+}
+
+/// Event sent by [`EmbassetPlugin`](crate::EmbassetPlugin) when an asset loaded through a
+/// [`DirectoryAssetIo`] watching for changes is modified, added or removed on disk.
+///
+/// Bevy 0.7 has no public way of reloading an asset that is already loaded, so it's up to the
+/// game to react, e.g. by reading the asset again through
+/// [`EmbassetIo::load_path`](bevy::asset::AssetIo::load_path).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetChanged {
+    /// Path of the changed asset, as it was loaded.
+    pub path: PathBuf,
+}
+
+impl DirectoryAssetIo {
+    /// Serve the files in the directory `root`, optionally watching them for changes.
+    pub fn new(root: impl Into<PathBuf>, watch_for_changes: bool) -> Self {
+        let io = DirectoryAssetIo {
+            root: root.into(),
+            watched: Mutex::new(None),
+        };
+        if watch_for_changes {
+            io.watch();
+        }
+        io
+    }
+
+    /// The directory the files are served from.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Full path of the file or directory at `path`, if inside the root directory.
+    fn full_path(&self, path: &Path) -> Option<PathBuf> {
+        let path = normalize(path);
+        (path != ".." && !path.starts_with("../")).then(|| self.root.join(path))
+    }
+
+    fn watch(&self) {
+        self.watched
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert_with(HashMap::default);
+    }
+
+    /// The watched paths of the files modified, added or removed since last checked.
+    pub(crate) fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut watched = self.watched.lock().unwrap_or_else(PoisonError::into_inner);
+        let watched = match watched.as_mut() {
+            Some(watched) => watched,
+            None => return vec![],
+        };
+        let mut changed = vec![];
+        for (path, modified) in watched.iter_mut() {
+            let current = self.full_path(path).and_then(|path| modified_time(&path));
+            if current != *modified {
+                *modified = current;
+                changed.push(path.clone());
+            }
+        }
+        changed.sort();
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+impl AssetIo for DirectoryAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            let full_path = self
+                .full_path(path)
+                .ok_or_else(|| AssetIoError::NotFound(path.to_path_buf()))?;
+            fs::read(&full_path).map_err(|err| {
+                if err.kind() == std::io::ErrorKind::NotFound {
+                    AssetIoError::NotFound(full_path)
+                } else {
+                    err.into()
+                }
+            })
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        let full_path = self
+            .full_path(path)
+            .ok_or_else(|| AssetIoError::NotFound(path.to_path_buf()))?;
+        let mut paths = fs::read_dir(full_path)?
+            .map(|entry| entry.map(|entry| path.join(entry.file_name())))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        Ok(Box::new(paths.into_iter()))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        matches!(self.full_path(path), Some(path) if path.is_dir())
+    }
+
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        if let Some(watched) = self
+            .watched
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_mut()
+        {
+            let modified = self.full_path(path).and_then(|path| modified_time(&path));
+            watched.insert(path.to_path_buf(), modified);
+        }
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        self.watch();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, File},
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime},
+    };
+
+    use bevy::{asset::AssetIo, utils::futures::now_or_never};
+
+    use super::DirectoryAssetIo;
+    use crate::test_util::{test_dir, write_files};
+
+    #[test]
+    fn serve_directory() {
+        let dir = test_dir("serve-directory");
+        write_files(
+            &dir,
+            &[
+                ("root/icon.png", "png"),
+                ("root/levels/1.ron", "ron"),
+                ("outside.png", "png"),
+            ],
+        );
+
+        let io = DirectoryAssetIo::new(dir.join("root"), false);
+        let load = |path: &str| now_or_never(io.load_path(Path::new(path))).unwrap();
+        assert_eq!(load("levels/1.ron").unwrap(), b"ron");
+        assert!(load("missing.png").is_err());
+        assert!(load("../outside.png").is_err());
+        assert!(io.is_directory(Path::new("levels")));
+        assert!(!io.is_directory(Path::new("icon.png")));
+        assert!(!io.is_directory(Path::new("..")));
+        assert_eq!(
+            io.read_directory(Path::new(""))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [PathBuf::from("icon.png"), PathBuf::from("levels")]
+        );
+        assert_eq!(
+            io.read_directory(Path::new("levels"))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [PathBuf::from("levels/1.ron")]
+        );
+        assert!(io.read_directory(Path::new("missing")).is_err());
+    }
+
+    #[test]
+    fn watch_for_changes() {
+        let dir = test_dir("directory-watch");
+        write_files(&dir, &[("icon.png", "png")]);
+
+        let unwatched = DirectoryAssetIo::new(&dir, false);
+        unwatched
+            .watch_path_for_changes(Path::new("icon.png"))
+            .unwrap();
+        let io = DirectoryAssetIo::new(&dir, true);
+        io.watch_path_for_changes(Path::new("icon.png")).unwrap();
+        io.watch_path_for_changes(Path::new("missing.png")).unwrap();
+        assert!(io.changed_paths().is_empty());
+
+        File::options()
+            .write(true)
+            .open(dir.join("icon.png"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        fs::write(dir.join("missing.png"), "png").unwrap();
+        assert_eq!(
+            io.changed_paths(),
+            [PathBuf::from("icon.png"), PathBuf::from("missing.png")]
+        );
+        assert!(io.changed_paths().is_empty());
+        assert!(unwatched.changed_paths().is_empty());
+    }
+}
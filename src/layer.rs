@@ -0,0 +1,43 @@
+use bevy::asset::AssetIo;
+use derive_more::DebugCustom;
+use smol_str::SmolStr;
+
+/// Name of the layer serving the [`RuntimeAssets`](crate::RuntimeAssets) of
+/// [`EmbassetIo`](crate::EmbassetIo).
+pub const RUNTIME_LAYER: &str = "runtime";
+
+/// Priority of [`RUNTIME_LAYER`], placing it above all other layers.
+pub const RUNTIME_PRIORITY: i32 = i32::MAX;
+
+/// Name of the layer serving the assets added to [`EmbassetIo`](crate::EmbassetIo) itself:
+/// embedded assets, packs, tables and disk assets.
+pub const EMBEDDED_LAYER: &str = "embedded";
+
+/// Priority of [`EMBEDDED_LAYER`].
+pub const EMBEDDED_PRIORITY: i32 = 0;
+
+/// Name of the layer serving assets through the default [`AssetIo`], if
+/// [`EmbassetIo`](crate::EmbassetIo) was created with one.
+pub const DEFAULT_LAYER: &str = "default";
+
+/// Priority of [`DEFAULT_LAYER`], placing it above the embedded assets.
+pub const DEFAULT_PRIORITY: i32 = 1;
+
+/// An [`AssetIo`] added as a layer of [`EmbassetIo`](crate::EmbassetIo).
+#[derive(DebugCustom)]
+#[debug(fmt = "Layer {{ name = {}, priority = {} }}", name, priority)]
+pub(crate) struct Layer {
+    pub(crate) name: SmolStr,
+    pub(crate) priority: i32,
+    pub(crate) asset_io: Box<dyn AssetIo>,
+}
+
+/// Where a layer gets its assets from.
+pub(crate) enum LayerSource<'a> {
+    /// The [`RuntimeAssets`](crate::RuntimeAssets) of the [`EmbassetIo`](crate::EmbassetIo).
+    Runtime,
+    /// The assets added to the [`EmbassetIo`](crate::EmbassetIo) itself.
+    Embedded,
+    /// An [`AssetIo`], i.e. the default one or one added as a layer.
+    AssetIo(&'a dyn AssetIo),
+}
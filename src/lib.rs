@@ -11,8 +11,40 @@
     missing_docs
 )]
 
+#[cfg(any(feature = "zip", feature = "tar"))]
+mod archive;
+mod compression;
+mod directory;
+mod layer;
+mod normalize;
+mod pack;
 mod plugin;
+mod runtime;
+mod table;
+#[cfg(feature = "tar")]
+mod tar_archive;
+#[cfg(test)]
+mod test_util;
+mod usage;
+#[cfg(feature = "zip")]
+mod zip_archive;
+pub use compression::Compression;
+pub use directory::{AssetChanged, DirectoryAssetIo};
+pub use layer::{
+    DEFAULT_LAYER, DEFAULT_PRIORITY, EMBEDDED_LAYER, EMBEDDED_PRIORITY, RUNTIME_LAYER,
+    RUNTIME_PRIORITY,
+};
+pub use pack::AssetPack;
+pub use table::{AssetTable, StaticAsset};
+#[cfg(feature = "tar")]
+pub use tar_archive::TarAssetIo;
+
+#[doc(hidden)]
+pub use phf;
 pub use plugin::EmbassetPlugin;
+pub use runtime::RuntimeAssets;
+#[cfg(feature = "zip")]
+pub use zip_archive::ZipAssetIo;
 
 pub use strum::{EnumCount, IntoEnumIterator};
 
@@ -27,8 +59,14 @@ use bevy::{
     utils::HashMap,
 };
 use derive_more::DebugCustom;
+use layer::{Layer, LayerSource};
+use normalize::{child_path, normalize};
 use smol_str::SmolStr;
-use std::path::{Path, PathBuf};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+use usage::UsageRecorder;
 
 /// Generates an enum for easy identification of assets - though **only usable with the standard bevy AssetIo**.
 ///
@@ -105,6 +143,21 @@ macro_rules! asset_ids {
 ///     }
 /// );
 /// ```
+///
+/// Instead of `root`, an `init` function can be given. The function is handed the
+/// [`EmbassetIo`](crate::EmbassetIo) wrapped by the struct, and must add the assets to it - e.g. a
+/// function generated by the build script:
+///
+/// ```rust
+/// assets!(
+///     pub enum GameAssets {
+///         Icon = "icon.png"
+///     },
+///     pub struct GameAssetsIo {
+///         init = add_embasset_assets
+///     }
+/// );
+/// ```
 #[macro_export]
 macro_rules! assets {
     (
@@ -116,6 +169,29 @@ macro_rules! assets {
         $io_vis:vis struct $AssetIo:ident {
             root=$root:literal
         }
+    ) => {
+        $crate::assets!(
+            $(#[$enum_docs])*
+            $enum_vis enum $AssetEnum {
+                $($(#[$metadata])* $variant=$asset,)*
+            },
+            $(#[$io_docs])*
+            $io_vis struct $AssetIo {
+                init=|#[allow(unused)] io: &mut $crate::EmbassetIo| {
+                    $(io.add_embedded_asset(std::path::Path::new($asset), include_bytes!(concat!($root, $asset)));)*
+                }
+            }
+        );
+    };
+    (
+        $(#[$enum_docs:meta])*
+        $enum_vis:vis enum $AssetEnum:ident {
+            $($(#[$metadata:meta])* $variant:ident=$asset:literal,)*
+        },
+        $(#[$io_docs:meta])*
+        $io_vis:vis struct $AssetIo:ident {
+            init=$init:expr
+        }
     ) => {
         paste::paste!{
             $(#[$enum_docs])*
@@ -217,7 +293,8 @@ macro_rules! assets {
                 #[doc = "Creates a new instance of " $AssetIo]
                 pub fn new() -> Self {
                     let mut io = $crate::EmbassetIo::new();
-                    $(io.add_embedded_asset(std::path::Path::new($asset), include_bytes!(concat!($root, $asset)));)*
+                    let init: fn(&mut $crate::EmbassetIo) = $init;
+                    init(&mut io);
                     Self(io)
                 }
             }
@@ -333,16 +410,43 @@ impl AssetIoAlternative {
             asset_io: Box::new(asset_io),
         }
     }
+
+    /// Remove the `path_start` part of `path`, which must start with it.
+    fn strip_path_start(&self, path: &Path) -> PathBuf {
+        let path = path.display().to_string();
+        let path = path
+            .strip_prefix(self.path_start.as_str())
+            .expect("path does not start with the defined path_start");
+        PathBuf::from(path)
+    }
 }
 
 /// Custom [`AssetServer`](bevy::asset::AssetServer), that can load assets embedded into the binary,
 /// or use other servers for handling the load.
 #[derive(DebugCustom)]
-#[debug(fmt = "EmbassetIo {{ handlers={:?} }}", handlers)]
+#[debug(fmt = "EmbassetIo {{ handlers={:?}, layers={:?} }}", handlers, layers)]
 pub struct EmbassetIo {
     default_io: Option<Box<dyn AssetIo>>,
     handlers: Vec<AssetIoAlternative>,
-    embedded_resources: HashMap<&'static Path, &'static [u8]>,
+    layers: Vec<Layer>,
+    /// The layer that most recently served each asset.
+    served: Mutex<HashMap<String, SmolStr>>,
+    embedded_resources: HashMap<String, EmbeddedAsset>,
+    packs: Vec<AssetPack>,
+    tables: Vec<AssetTable>,
+    runtime: RuntimeAssets,
+    usage: Option<UsageRecorder>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum EmbeddedAsset {
+    /// Data embedded into the binary.
+    Data {
+        data: &'static [u8],
+        compression: Compression,
+    },
+    /// A file read from disk whenever the asset is loaded.
+    File(&'static Path),
 }
 
 impl Default for EmbassetIo {
@@ -359,7 +463,13 @@ impl EmbassetIo {
         EmbassetIo {
             default_io: Some(default_io),
             handlers: Default::default(),
+            layers: Default::default(),
+            served: Default::default(),
             embedded_resources: Default::default(),
+            packs: Default::default(),
+            tables: Default::default(),
+            runtime: Default::default(),
+            usage: None,
         }
     }
 
@@ -370,7 +480,13 @@ impl EmbassetIo {
         EmbassetIo {
             default_io: None,
             handlers: Default::default(),
+            layers: Default::default(),
+            served: Default::default(),
             embedded_resources: Default::default(),
+            packs: Default::default(),
+            tables: Default::default(),
+            runtime: Default::default(),
+            usage: None,
         }
     }
 
@@ -380,23 +496,241 @@ impl EmbassetIo {
         self
     }
 
+    /// Add an [`AssetIo`](bevy::asset::AssetIo) as a layer named `name`, e.g. a folder of mods
+    /// overriding the embedded assets.
+    ///
+    /// Paths not matching any handler are looked up in the layers from the highest `priority` to
+    /// the lowest, and the first layer holding the asset serves it. The assets added to the
+    /// `EmbassetIo` itself form the layer [`EMBEDDED_LAYER`] with priority
+    /// [`EMBEDDED_PRIORITY`], and the default `AssetIo` (if any) the layer [`DEFAULT_LAYER`]
+    /// with priority [`DEFAULT_PRIORITY`]. The [`RuntimeAssets`] form the layer
+    /// [`RUNTIME_LAYER`], which is always looked up first. Of other layers with the same
+    /// priority, the one added last is looked up first, and added layers are looked up before the
+    /// built-in ones.
+    ///
+    /// Reading a directory lists the content of all layers holding it.
+    pub fn add_layer<T: AssetIo>(&mut self, name: &str, priority: i32, asset_io: T) -> &mut Self {
+        self.layers.push(Layer {
+            name: SmolStr::new(name),
+            priority,
+            asset_io: Box::new(asset_io),
+        });
+        self
+    }
+
+    /// Name of the layer that served the asset at `path` the last time it was loaded.
+    ///
+    /// Returns `None` if the asset hasn't been loaded, or was loaded through a handler.
+    pub fn served_by(&self, path: &Path) -> Option<String> {
+        self.served
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(&normalize(path))
+            .map(ToString::to_string)
+    }
+
     /// Add a slice of bytes as a resource using the specified Path.
+    ///
+    /// The path is normalized, so e.g. `textures\\ship.png` and `./textures/ship.png` are both
+    /// loaded as `textures/ship.png`.
     pub fn add_embedded_asset(&mut self, path: &'static Path, data: &'static [u8]) -> &mut Self {
-        self.embedded_resources.insert(path, data);
+        self.add_compressed_asset(path, Compression::None, data)
+    }
+
+    /// Add a slice of compressed bytes as a resource using the specified Path.
+    ///
+    /// The data is decompressed whenever the asset is loaded.
+    pub fn add_compressed_asset(
+        &mut self,
+        path: &'static Path,
+        compression: Compression,
+        data: &'static [u8],
+    ) -> &mut Self {
+        self.embedded_resources
+            .insert(normalize(path), EmbeddedAsset::Data { data, compression });
+        self
+    }
+
+    /// Add a pack of assets, as generated by the build script.
+    ///
+    /// Assets added individually take precedence over assets in a pack, and assets in packs
+    /// added later take precedence over those added earlier.
+    pub fn add_pack(&mut self, pack: AssetPack) -> &mut Self {
+        self.packs.push(pack);
+        self
+    }
+
+    /// Add a table of assets, as generated by the build script.
+    ///
+    /// Assets added individually take precedence over assets in a table, and assets in tables
+    /// take precedence over assets in packs. Tables added later take precedence over those added
+    /// earlier.
+    pub fn add_table(&mut self, table: AssetTable) -> &mut Self {
+        self.tables.push(table);
         self
     }
 
-    /// Get the data from the asset matching the path provided.
+    /// Add owned or reference counted data as a resource using the specified Path, e.g. data
+    /// generated or downloaded at startup.
+    ///
+    /// The asset is added to the [`RuntimeAssets`] of this `EmbassetIo`, replacing any asset
+    /// previously added at the same path. Use [`runtime_assets`](EmbassetIo::runtime_assets) to
+    /// add, replace and remove assets after the `EmbassetIo` has been handed to the
+    /// [`AssetServer`](bevy::asset::AssetServer).
+    pub fn add_owned_asset(
+        &mut self,
+        path: impl AsRef<Path>,
+        data: impl Into<Arc<[u8]>>,
+    ) -> &mut Self {
+        self.runtime.insert(path, data);
+        self
+    }
+
+    /// Handle to the assets added at runtime, shared with this `EmbassetIo`.
+    ///
+    /// [`EmbassetPlugin`] inserts the handle as a resource.
+    pub fn runtime_assets(&self) -> RuntimeAssets {
+        self.runtime.clone()
+    }
+
+    /// Add a file on disk as a resource using the specified Path.
+    ///
+    /// The file is read whenever the asset is loaded, meaning `source` should be absolute for
+    /// the asset to be found regardless of the working directory. Used by the code generated by
+    /// the build helpers, when configured to load assets from disk during development.
+    pub fn add_disk_asset(&mut self, path: &'static Path, source: &'static Path) -> &mut Self {
+        self.embedded_resources
+            .insert(normalize(path), EmbeddedAsset::File(source));
+        self
+    }
+
+    /// Record the paths of all assets and directories successfully requested from this
+    /// `EmbassetIo` to `file`, without the prefix of any handler they were requested through.
+    ///
+    /// The file is updated as soon as a new path is requested, and paths recorded by earlier
+    /// sessions are kept - delete the file to start over. The build helpers can use it as the
+    /// list of assets to embed (`EmbassetBuilder::add_used_assets`), so a release build only
+    /// embeds the assets actually used.
+    pub fn record_usage(&mut self, file: impl Into<PathBuf>) -> &mut Self {
+        self.usage = Some(UsageRecorder::new(file.into()));
+        self
+    }
+
+    /// Get the (decompressed) data from the asset matching the path provided.
     ///
     /// # Errors
     ///
-    /// This will returns an error if the path is not known or embedded.
+    /// This will returns an error if the path is not known or embedded, or if the embedded data
+    /// could not be decompressed.
     #[doc(hidden)]
     pub fn load_embedded_path_sync(&self, path: &Path) -> Result<Vec<u8>, AssetIoError> {
+        let key = normalize(path);
+        let asset = match self.embedded_resources.get(&key) {
+            Some(asset) => asset,
+            None => {
+                let data = self
+                    .tables
+                    .iter()
+                    .rev()
+                    .find_map(|table| table.load(&key))
+                    .or_else(|| self.packs.iter().rev().find_map(|pack| pack.load(&key)));
+                return match data {
+                    Some(data) => Ok(data?),
+                    None => Err(AssetIoError::NotFound(path.to_path_buf())),
+                };
+            }
+        };
+        match asset {
+            EmbeddedAsset::Data { data, compression } => Ok(compression.decompress(data)?),
+            EmbeddedAsset::File(source) => std::fs::read(source).map_err(|err| {
+                if err.kind() == std::io::ErrorKind::NotFound {
+                    AssetIoError::NotFound(source.to_path_buf())
+                } else {
+                    err.into()
+                }
+            }),
+        }
+    }
+
+    /// Paths of the watched assets that changed since last checked, as served by a
+    /// [`DirectoryAssetIo`] added as a handler or a layer.
+    pub(crate) fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut changed = vec![];
+        for config in &self.handlers {
+            if let Some(io) = config.asset_io.downcast_ref::<DirectoryAssetIo>() {
+                changed.extend(
+                    io.changed_paths().into_iter().map(|path| {
+                        PathBuf::from(format!("{}{}", config.path_start, path.display()))
+                    }),
+                );
+            }
+        }
+        for layer in &self.layers {
+            if let Some(io) = layer.asset_io.downcast_ref::<DirectoryAssetIo>() {
+                changed.extend(io.changed_paths());
+            }
+        }
+        changed
+    }
+
+    /// The path of the asset at `path` to record usage for, without the prefix of any handler.
+    fn usage_path(&self, path: &Path) -> PathBuf {
+        match self
+            .handlers
+            .iter()
+            .find(|h| path.starts_with(h.path_start.as_str()))
+        {
+            Some(config) => config.strip_path_start(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// The layers, in the order they are looked up.
+    fn layers(&self) -> Vec<(&str, LayerSource<'_>)> {
+        let mut layers = vec![(RUNTIME_LAYER, RUNTIME_PRIORITY, LayerSource::Runtime)];
+        layers.extend(self.layers.iter().rev().map(|layer| {
+            (
+                layer.name.as_str(),
+                layer.priority,
+                LayerSource::AssetIo(layer.asset_io.as_ref()),
+            )
+        }));
+        if let Some(default_io) = &self.default_io {
+            layers.push((
+                DEFAULT_LAYER,
+                DEFAULT_PRIORITY,
+                LayerSource::AssetIo(default_io.as_ref()),
+            ));
+        }
+        layers.push((EMBEDDED_LAYER, EMBEDDED_PRIORITY, LayerSource::Embedded));
+        layers.sort_by_key(|(_, priority, _)| std::cmp::Reverse(*priority));
+        layers
+            .into_iter()
+            .map(|(name, _, source)| (name, source))
+            .collect()
+    }
+
+    fn is_embedded_directory(&self, path: &Path) -> bool {
+        let path = normalize(path);
+        if self
+            .tables
+            .iter()
+            .any(|table| table.directory(&path).is_some())
+        {
+            return true;
+        }
+        if path.is_empty() {
+            return !self.embedded_resources.is_empty()
+                || self.packs.iter().any(|pack| !pack.is_empty());
+        }
+        let as_folder = format!("{}/", path);
         self.embedded_resources
-            .get(path)
-            .map(|b| b.to_vec())
-            .ok_or_else(|| bevy::asset::AssetIoError::NotFound(path.to_path_buf()))
+            .keys()
+            .any(|loaded_path| loaded_path.starts_with(&as_folder))
+            || self
+                .packs
+                .iter()
+                .any(|pack| pack.paths_starting_with(&as_folder).next().is_some())
     }
 }
 
@@ -405,12 +739,8 @@ async fn load_path_via_assetio<'a>(
     config: &'a AssetIoAlternative,
     bevasset: &'a EmbassetIo,
 ) -> Result<Vec<u8>, AssetIoError> {
-    // first remove the path_start part of the path
-    let path = path.display().to_string();
-    let path = path
-        .strip_prefix(config.path_start.as_str())
-        .expect("path does not start with the defined path_start");
-    let path = Path::new(path);
+    let path = config.strip_path_start(path);
+    let path = path.as_path();
 
     // now load using the handler
     trace!(?path, path_start=?config.path_start, "load asset via AssetIo");
@@ -441,54 +771,37 @@ async fn load_path<'a>(path: &'a Path, bevasset: &'a EmbassetIo) -> Result<Vec<u
     {
         load_path_via_assetio(path, config, bevasset).await
     } else {
-        trace!(?path, "load asset via default AssetIo");
-        match &bevasset.default_io {
-            Some(default_io) => match default_io.load_path(path).await {
-                r @ Ok(_) => {
-                    trace!(?path, "loaded");
-                    r
+        let mut last_err = None;
+        for (layer, source) in bevasset.layers() {
+            trace!(?path, %layer, "load asset via layer");
+            let r = match source {
+                LayerSource::Runtime => bevasset
+                    .runtime
+                    .load(&normalize(path))
+                    .map(|data| data.to_vec())
+                    .ok_or_else(|| AssetIoError::NotFound(path.to_path_buf())),
+                LayerSource::Embedded => bevasset.load_embedded_path_sync(path),
+                LayerSource::AssetIo(asset_io) => asset_io.load_path(path).await,
+            };
+            match r {
+                Ok(data) => {
+                    trace!(?path, %layer, "loaded");
+                    bevasset
+                        .served
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .insert(normalize(path), SmolStr::new(layer));
+                    return Ok(data);
                 }
                 Err(err) => {
-                    info!(
-                        ?err,
-                        ?path,
-                        "failed loading asset using default AssetIo, fallback to embedded resource"
-                    );
-                    match bevasset
-                        .embedded_resources
-                        .get(path)
-                        .map(|b| b.to_vec())
-                        .ok_or_else(|| bevy::asset::AssetIoError::NotFound(path.to_path_buf()))
-                    {
-                        r @ Ok(_) => {
-                            trace!(?path, "loaded");
-                            r
-                        }
-                        Err(err) => {
-                            warn!(?err, ?path, "failed loading asset");
-                            Err(err)
-                        }
-                    }
-                }
-            },
-            None => {
-                match bevasset
-                    .embedded_resources
-                    .get(path)
-                    .map(|b| b.to_vec())
-                    .ok_or_else(|| bevy::asset::AssetIoError::NotFound(path.to_path_buf()))
-                {
-                    r @ Ok(_) => {
-                        trace!(?path, "loaded");
-                        r
-                    }
-                    Err(err) => {
-                        warn!(?err, ?path, "failed loading asset");
-                        Err(err)
-                    }
+                    trace!(?err, ?path, %layer, "failed loading asset via layer, trying next");
+                    last_err = Some(err);
                 }
             }
         }
+        let err = last_err.unwrap_or_else(|| AssetIoError::NotFound(path.to_path_buf()));
+        warn!(?err, ?path, "failed loading asset");
+        Err(err)
     }
 }
 
@@ -497,26 +810,52 @@ fn read_embedded_directory(
     path: &Path,
 ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
     trace!(?path, "read directory as embedded resource");
-    if bevasset.is_directory(path) {
-        #[allow(clippy::needless_collect)]
-        let paths: Vec<_> = bevasset
+    if bevasset.is_embedded_directory(path) {
+        let directory = normalize(path);
+        let as_folder = match directory.as_str() {
+            "" => String::new(),
+            directory => format!("{}/", directory),
+        };
+        let mut paths: Vec<_> = bevasset
             .embedded_resources
             .keys()
-            .filter(|loaded_path| loaded_path.starts_with(path))
-            .map(|t| t.to_path_buf())
+            .filter_map(|loaded_path| child_path(&directory, loaded_path))
+            .map(PathBuf::from)
+            .chain(
+                bevasset
+                    .packs
+                    .iter()
+                    .flat_map(|pack| pack.paths_starting_with(&as_folder))
+                    .filter_map(|loaded_path| child_path(&directory, loaded_path))
+                    .map(PathBuf::from),
+            )
+            .chain(
+                bevasset
+                    .tables
+                    .iter()
+                    .filter_map(|table| table.directory(&directory))
+                    .flatten()
+                    .map(PathBuf::from),
+            )
             .collect();
+        paths.sort_unstable();
+        paths.dedup();
         trace!(?path, "loaded");
         Ok(Box::new(paths.into_iter()))
     } else {
-        let err = AssetIoError::Io(std::io::ErrorKind::NotFound.into());
-        warn!(?err, ?path, "failed read directory");
-        Err(err)
+        Err(AssetIoError::Io(std::io::ErrorKind::NotFound.into()))
     }
 }
 
 impl AssetIo for EmbassetIo {
     fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
-        Box::pin(load_path(path, self))
+        Box::pin(async move {
+            let result = load_path(path, self).await;
+            if let (Ok(_), Some(usage)) = (&result, &self.usage) {
+                usage.asset(&normalize(&self.usage_path(path)));
+            }
+            result
+        })
     }
 
     fn read_directory(
@@ -528,64 +867,63 @@ impl AssetIo for EmbassetIo {
             .iter()
             .find(|h| path.starts_with(h.path_start.as_str()))
         {
-            // first remove the path_start part of the path
-            let path = path.display().to_string();
-            let path = path
-                .strip_prefix(config.path_start.as_str())
-                .expect("path does not start with the defined path_start");
-            let path = Path::new(path);
-            // pass call to handler
+            let path = config.strip_path_start(path);
             trace!(?path, path_start=?config.path_start, "read directory via handler");
-            config.asset_io.read_directory(path)
+            let result = config.asset_io.read_directory(&path);
+            if let (Ok(_), Some(usage)) = (&result, &self.usage) {
+                usage.directory(&normalize(&path));
+            }
+            result
         } else {
-            match &self.default_io {
-                Some(default_io) => {
-                    trace!(?path, "read directory via default AssetIo");
-                    match default_io.read_directory(path) {
-                        r @ Ok(_) => r,
-                        Err(err) => {
-                            info!(
-                                ?err,
-                                ?path,
-                                "failed read directory via default AssetIo, fallback to embedded resource"
-                            );
-                            read_embedded_directory(self, path)
-                        }
+            let mut paths = vec![];
+            let mut found = false;
+            let mut last_err = None;
+            for (layer, source) in self.layers() {
+                trace!(?path, %layer, "read directory via layer");
+                let r: Result<Box<dyn Iterator<Item = PathBuf>>, _> = match source {
+                    LayerSource::Runtime => match self.runtime.read_directory(&normalize(path)) {
+                        Some(paths) => Ok(Box::new(paths.into_iter())),
+                        None => Err(AssetIoError::NotFound(path.to_path_buf())),
+                    },
+                    LayerSource::Embedded => read_embedded_directory(self, path),
+                    LayerSource::AssetIo(asset_io) => asset_io.read_directory(path),
+                };
+                match r {
+                    Ok(layer_paths) => {
+                        found = true;
+                        paths.extend(layer_paths);
                     }
+                    Err(err) => last_err = Some(err),
                 }
-                None => read_embedded_directory(self, path),
             }
+            if !found {
+                let err = last_err.unwrap_or_else(|| AssetIoError::NotFound(path.to_path_buf()));
+                warn!(?err, ?path, "failed read directory");
+                return Err(err);
+            }
+            paths.sort_unstable();
+            paths.dedup();
+            if let Some(usage) = &self.usage {
+                usage.directory(&normalize(path));
+            }
+            Ok(Box::new(paths.into_iter()))
         }
     }
 
     fn is_directory(&self, path: &Path) -> bool {
-        let is_directory = if let Some(config) = self
+        if let Some(config) = self
             .handlers
             .iter()
             .find(|h| path.starts_with(h.path_start.as_str()))
         {
-            config.asset_io.is_directory(path)
+            config.asset_io.is_directory(&config.strip_path_start(path))
         } else {
-            match &self.default_io {
-                Some(default_io) => default_io.is_directory(path),
-                None => {
-                    let is_directory = if let Some(config) = self
-                        .handlers
-                        .iter()
-                        .find(|h| path.starts_with(h.path_start.as_str()))
-                    {
-                        config.asset_io.is_directory(path)
-                    } else {
-                        let as_folder = path.join("");
-                        self.embedded_resources.keys().any(|loaded_path| {
-                            loaded_path.starts_with(&as_folder) && loaded_path != &path
-                        })
-                    };
-                    is_directory
-                }
-            }
-        };
-        is_directory
+            self.layers().into_iter().any(|(_, source)| match source {
+                LayerSource::Runtime => self.runtime.is_directory(&normalize(path)),
+                LayerSource::Embedded => self.is_embedded_directory(path),
+                LayerSource::AssetIo(asset_io) => asset_io.is_directory(path),
+            })
+        }
     }
 
     fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
@@ -594,26 +932,26 @@ impl AssetIo for EmbassetIo {
             .iter()
             .find(|h| path.starts_with(h.path_start.as_str()))
         {
-            config.asset_io.watch_path_for_changes(path)
+            config
+                .asset_io
+                .watch_path_for_changes(&config.strip_path_start(path))
         } else {
-            match &self.default_io {
-                Some(default_io) => match default_io.watch_path_for_changes(path) {
-                    r @ Ok(_) => r,
-                    Err(_) => Ok(()),
-                },
-                None => Ok(()),
+            for (_, source) in self.layers() {
+                if let LayerSource::AssetIo(asset_io) = source {
+                    let _ = asset_io.watch_path_for_changes(path);
+                }
             }
+            Ok(())
         }
     }
 
     fn watch_for_changes(&self) -> Result<(), AssetIoError> {
-        match &self.default_io {
-            Some(default_io) => match default_io.watch_for_changes() {
-                r @ Ok(_) => r,
-                Err(_) => Ok(()),
-            },
-            None => Ok(()),
+        for (_, source) in self.layers() {
+            if let LayerSource::AssetIo(asset_io) = source {
+                let _ = asset_io.watch_for_changes();
+            }
         }
+        Ok(())
     }
 }
 
@@ -653,6 +991,134 @@ mod tests {
             .is_err());
     }
 
+    #[cfg(all(feature = "build", feature = "deflate"))]
+    #[test]
+    fn load_compressed_path() {
+        use crate::Compression;
+
+        let data = b"some highly compressible data data data data data".to_vec();
+        let compressed = Box::leak(
+            Compression::Deflate
+                .compress(&data)
+                .unwrap()
+                .into_boxed_slice(),
+        );
+        let mut embedded = EmbassetIo::new();
+        embedded.add_compressed_asset(Path::new("asset.txt"), Compression::Deflate, compressed);
+
+        assert_eq!(
+            embedded
+                .load_embedded_path_sync(Path::new("asset.txt"))
+                .unwrap(),
+            data
+        );
+        assert_eq!(
+            bevy::utils::futures::now_or_never(embedded.load_path(Path::new("asset.txt")))
+                .unwrap()
+                .unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn load_windows_style_path() {
+        let mut embedded = EmbassetIo::new();
+        embedded.add_embedded_asset(Path::new("textures\\ship.png"), &[1, 2, 3]);
+        embedded.add_embedded_asset(Path::new("./sounds//bg.ogg"), &[4, 5, 6]);
+
+        for path in [
+            "textures/ship.png",
+            "textures\\ship.png",
+            "./textures/ship.png",
+            ".\\textures\\\\ship.png",
+        ] {
+            assert_eq!(
+                embedded.load_embedded_path_sync(Path::new(path)).unwrap(),
+                [1, 2, 3]
+            );
+        }
+        assert_eq!(
+            embedded
+                .load_embedded_path_sync(Path::new("sounds\\bg.ogg"))
+                .unwrap(),
+            [4, 5, 6]
+        );
+        assert!(embedded.is_directory(Path::new("textures\\")));
+        assert_eq!(
+            embedded
+                .read_directory(Path::new(".\\textures"))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            vec![Path::new("textures/ship.png")]
+        );
+    }
+
+    #[test]
+    fn load_disk_asset() {
+        let source = Box::leak(
+            crate::test_util::test_dir("disk-asset")
+                .join("asset.txt")
+                .into_boxed_path(),
+        );
+        std::fs::write(&*source, [1, 2, 3]).unwrap();
+        let mut embedded = EmbassetIo::new();
+        embedded.add_disk_asset(Path::new("asset.txt"), source);
+        embedded.add_disk_asset(Path::new("missing.txt"), Path::new("/does/not/exist.txt"));
+
+        assert_eq!(
+            embedded
+                .load_embedded_path_sync(Path::new("asset.txt"))
+                .unwrap(),
+            [1, 2, 3]
+        );
+        std::fs::write(&*source, [4, 5, 6]).unwrap();
+        assert_eq!(
+            embedded
+                .load_embedded_path_sync(Path::new("asset.txt"))
+                .unwrap(),
+            [4, 5, 6]
+        );
+        assert!(matches!(
+            embedded.load_embedded_path_sync(Path::new("missing.txt")),
+            Err(bevy::asset::AssetIoError::NotFound(_))
+        ));
+    }
+
+    #[cfg(feature = "build")]
+    #[test]
+    fn load_from_pack() {
+        use crate::{pack::write_pack, AssetPack, Compression};
+
+        let pack = write_pack(&[
+            ("asset.png", Compression::None, &[1, 2, 3]),
+            ("directory/asset.png", Compression::None, &[4, 5, 6]),
+        ]);
+        let mut embedded = EmbassetIo::new();
+        embedded.add_pack(AssetPack::new(Box::leak(pack.into_boxed_slice())).unwrap());
+        embedded.add_embedded_asset(Path::new("directory/asset2.png"), &[7]);
+
+        assert_eq!(
+            embedded
+                .load_embedded_path_sync(Path::new(".\\directory\\asset.png"))
+                .unwrap(),
+            [4, 5, 6]
+        );
+        assert!(embedded
+            .load_embedded_path_sync(Path::new("directory"))
+            .is_err());
+        assert!(embedded.is_directory(Path::new("directory")));
+        assert_eq!(
+            embedded
+                .read_directory(Path::new("directory"))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [
+                Path::new("directory/asset.png"),
+                Path::new("directory/asset2.png")
+            ]
+        );
+    }
+
     #[test]
     fn is_directory() {
         let mut embedded = EmbassetIo::new();
@@ -683,4 +1149,246 @@ mod tests {
         list.sort();
         assert_eq!(list, vec!["directory/asset.png", "directory/asset2.png"]);
     }
+
+    #[test]
+    fn record_usage() {
+        let file = crate::test_util::test_dir("usage").join("used.txt");
+        let mut embedded = EmbassetIo::new();
+        embedded.add_embedded_asset(Path::new("asset.png"), &[]);
+        embedded.add_embedded_asset(Path::new("directory/asset.png"), &[]);
+        embedded.record_usage(&file);
+
+        let load = |path| bevy::utils::futures::now_or_never(embedded.load_path(Path::new(path)));
+        assert!(load("directory\\asset.png").unwrap().is_ok());
+        assert!(load("missing.png").unwrap().is_err());
+        assert!(embedded.read_directory(Path::new("directory")).is_ok());
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "directory/\ndirectory/asset.png\n"
+        );
+
+        let mut embedded = EmbassetIo::new();
+        embedded.add_embedded_asset(Path::new("asset.png"), &[]);
+        embedded.record_usage(&file);
+        assert!(
+            bevy::utils::futures::now_or_never(embedded.load_path(Path::new("asset.png")))
+                .unwrap()
+                .is_ok()
+        );
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "asset.png\ndirectory/\ndirectory/asset.png\n"
+        );
+    }
+
+    #[test]
+    fn record_usage_through_handler() {
+        let file = crate::test_util::test_dir("usage-handler").join("used.txt");
+        let mut game_assets = EmbassetIo::new();
+        game_assets.add_embedded_asset(Path::new("levels/1.ron"), &[]);
+        let mut embedded = EmbassetIo::new();
+        embedded.add_handler(super::AssetIoAlternative::new(
+            "GameAssets://",
+            game_assets,
+            false,
+        ));
+        embedded.record_usage(&file);
+
+        let load = |path| bevy::utils::futures::now_or_never(embedded.load_path(Path::new(path)));
+        assert!(load("GameAssets://levels/1.ron").unwrap().is_ok());
+        assert!(load("GameAssets://missing.ron").unwrap().is_err());
+        assert!(embedded
+            .read_directory(Path::new("GameAssets://levels"))
+            .is_ok());
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "levels/\nlevels/1.ron\n"
+        );
+    }
+
+    #[test]
+    fn runtime_assets() {
+        let mut embedded = EmbassetIo::new();
+        embedded.add_embedded_asset(Path::new("asset.png"), &[1]);
+        embedded.add_owned_asset(std::path::PathBuf::from("levels/1.ron"), vec![2]);
+        let assets = embedded.runtime_assets();
+        let load =
+            |path| bevy::utils::futures::now_or_never(embedded.load_path(Path::new(path))).unwrap();
+
+        assert!(embedded.is_directory(Path::new("levels")));
+        assets.insert("asset.png", vec![3]);
+        assets.insert("levels/2.ron", vec![4]);
+        assert_eq!(load("asset.png").unwrap(), [3]);
+        assert_eq!(
+            embedded
+                .read_directory(Path::new("levels"))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [Path::new("levels/1.ron"), Path::new("levels/2.ron")]
+        );
+
+        assets.remove("asset.png");
+        assets.remove("levels/1.ron");
+        assert_eq!(load("asset.png").unwrap(), [1]);
+        assert!(load("levels/1.ron").is_err());
+    }
+
+    #[test]
+    fn runtime_assets_above_layers() {
+        let mut mods = EmbassetIo::new();
+        mods.add_embedded_asset(Path::new("levels/1.ron"), &[2]);
+        let mut embedded = EmbassetIo::new();
+        embedded
+            .add_owned_asset("levels/1.ron", vec![1])
+            .add_layer("mods", i32::MAX, mods);
+
+        let load = |path| {
+            bevy::utils::futures::now_or_never(embedded.load_path(Path::new(path)))
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(load("levels/1.ron"), [1]);
+        assert_eq!(
+            embedded.served_by(Path::new("levels/1.ron")).as_deref(),
+            Some(super::RUNTIME_LAYER)
+        );
+        embedded.runtime_assets().remove("levels/1.ron");
+        assert_eq!(load("levels/1.ron"), [2]);
+        assert_eq!(
+            embedded.served_by(Path::new("levels/1.ron")).as_deref(),
+            Some("mods")
+        );
+    }
+
+    #[test]
+    fn layers() {
+        let mut patch = EmbassetIo::new();
+        patch.add_embedded_asset(Path::new("levels/1.ron"), &[2]);
+        let mut mods = EmbassetIo::new();
+        mods.add_embedded_asset(Path::new("levels/1.ron"), &[3]);
+        mods.add_embedded_asset(Path::new("levels/mod.ron"), &[3]);
+        let mut fallback = EmbassetIo::new();
+        fallback.add_embedded_asset(Path::new("missing.png"), &[4]);
+
+        let mut embedded = EmbassetIo::new();
+        embedded.add_embedded_asset(Path::new("levels/1.ron"), &[1]);
+        embedded.add_embedded_asset(Path::new("levels/2.ron"), &[1]);
+        embedded
+            .add_layer("mods", 20, mods)
+            .add_layer("patch", 10, patch)
+            .add_layer("fallback", -1, fallback);
+
+        let load = |path| {
+            bevy::utils::futures::now_or_never(embedded.load_path(Path::new(path)))
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(load("levels/1.ron"), [3]);
+        assert_eq!(load("levels/2.ron"), [1]);
+        assert_eq!(load("missing.png"), [4]);
+        assert_eq!(
+            embedded.served_by(Path::new("levels/1.ron")).as_deref(),
+            Some("mods")
+        );
+        assert_eq!(
+            embedded.served_by(Path::new("levels/2.ron")).as_deref(),
+            Some(super::EMBEDDED_LAYER)
+        );
+        assert_eq!(
+            embedded.served_by(Path::new("missing.png")).as_deref(),
+            Some("fallback")
+        );
+        assert!(embedded.served_by(Path::new("levels/mod.ron")).is_none());
+
+        assert_eq!(
+            embedded
+                .read_directory(Path::new("levels"))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [
+                Path::new("levels/1.ron"),
+                Path::new("levels/2.ron"),
+                Path::new("levels/mod.ron")
+            ]
+        );
+        assert!(embedded.is_directory(Path::new("levels")));
+        assert!(embedded.read_directory(Path::new("sounds")).is_err());
+    }
+
+    #[test]
+    fn merged_directory_listing() {
+        let dir = crate::test_util::test_dir("merged-listing");
+        crate::test_util::write_files(
+            &dir,
+            &[
+                ("mod.png", "png"),
+                ("levels/1.ron", "1"),
+                ("levels/lava/3.ron", "3"),
+            ],
+        );
+
+        let mut embedded = EmbassetIo::new();
+        embedded.add_embedded_asset(Path::new("icon.png"), &[]);
+        embedded.add_embedded_asset(Path::new("levels/1.ron"), &[]);
+        embedded.add_embedded_asset(Path::new("levels/ice/2.ron"), &[]);
+        embedded.add_layer("mods", 10, super::DirectoryAssetIo::new(&dir, false));
+
+        let read = |path| {
+            embedded
+                .read_directory(Path::new(path))
+                .unwrap()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            read(""),
+            [
+                Path::new("icon.png"),
+                Path::new("levels"),
+                Path::new("mod.png")
+            ]
+        );
+        assert_eq!(
+            read("levels"),
+            [
+                Path::new("levels/1.ron"),
+                Path::new("levels/ice"),
+                Path::new("levels/lava")
+            ]
+        );
+        assert!(embedded.is_directory(Path::new("levels/ice")));
+        assert!(embedded.is_directory(Path::new("levels/lava")));
+        assert_eq!(read("levels/lava"), [Path::new("levels/lava/3.ron")]);
+    }
+
+    #[test]
+    fn directory_handler() {
+        let dir = crate::test_util::test_dir("directory-handler");
+        crate::test_util::write_files(&dir, &[("levels/1.ron", "1")]);
+
+        let mut embedded = EmbassetIo::new();
+        embedded.add_handler(super::AssetIoAlternative::new(
+            "user://",
+            super::DirectoryAssetIo::new(&dir, true),
+            false,
+        ));
+        assert!(embedded.is_directory(Path::new("user://levels")));
+        assert_eq!(
+            embedded
+                .read_directory(Path::new("user://levels"))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [Path::new("levels/1.ron")]
+        );
+        embedded
+            .watch_path_for_changes(Path::new("user://levels/1.ron"))
+            .unwrap();
+        assert!(embedded.changed_paths().is_empty());
+        std::fs::File::options()
+            .write(true)
+            .open(dir.join("levels/1.ron"))
+            .unwrap()
+            .set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+        assert_eq!(embedded.changed_paths(), [Path::new("user://levels/1.ron")]);
+    }
 }
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+/// Normalizes an asset path to the canonical form used as key for embedded assets.
+///
+/// Backslashes are treated as separators, and the path is made relative using `/` as the only
+/// separator, without any empty, `.` or resolvable `..` components.
+pub(crate) fn normalize_path(path: &str) -> String {
+    let mut components: Vec<&str> = vec![];
+    for component in path.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." if matches!(components.last(), Some(last) if *last != "..") => {
+                components.pop();
+            }
+            component => components.push(component),
+        }
+    }
+    components.join("/")
+}
+
+/// Same as [`normalize_path`], for a [`Path`].
+pub(crate) fn normalize(path: &Path) -> String {
+    normalize_path(&path.to_string_lossy())
+}
+
+/// The child of `directory` holding `path`, both normalized, if `path` is below `directory`.
+///
+/// The child is `path` itself when directly in `directory`, and otherwise the subdirectory of
+/// `directory` that `path` is in.
+pub(crate) fn child_path<'a>(directory: &str, path: &'a str) -> Option<&'a str> {
+    let rest = if directory.is_empty() {
+        path
+    } else {
+        path.strip_prefix(directory)?.strip_prefix('/')?
+    };
+    if rest.is_empty() {
+        return None;
+    }
+    let end = path.len() - rest.len() + rest.find('/').unwrap_or(rest.len());
+    Some(&path[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{child_path, normalize, normalize_path};
+
+    #[test]
+    fn forward_slashes() {
+        assert_eq!(normalize_path("textures/ship.png"), "textures/ship.png");
+        assert_eq!(normalize_path("textures\\ship.png"), "textures/ship.png");
+        assert_eq!(
+            normalize(Path::new("textures\\sub\\ship.png")),
+            "textures/sub/ship.png"
+        );
+    }
+
+    #[test]
+    fn collapse_components() {
+        assert_eq!(normalize_path("./textures/ship.png"), "textures/ship.png");
+        assert_eq!(
+            normalize_path(".\\textures\\\\ship.png"),
+            "textures/ship.png"
+        );
+        assert_eq!(normalize_path("textures//./ship.png"), "textures/ship.png");
+        assert_eq!(normalize_path("/textures/ship.png"), "textures/ship.png");
+        assert_eq!(normalize_path("textures/"), "textures");
+        assert_eq!(
+            normalize_path("textures/old/../ship.png"),
+            "textures/ship.png"
+        );
+        assert_eq!(normalize_path("../ship.png"), "../ship.png");
+        assert_eq!(normalize_path("./"), "");
+    }
+
+    #[test]
+    fn children_of_directory() {
+        assert_eq!(child_path("", "ship.png"), Some("ship.png"));
+        assert_eq!(child_path("", "textures/ship.png"), Some("textures"));
+        assert_eq!(
+            child_path("textures", "textures/ui/ship.png"),
+            Some("textures/ui")
+        );
+        assert_eq!(
+            child_path("textures", "textures/ship.png"),
+            Some("textures/ship.png")
+        );
+        assert_eq!(child_path("textures", "textures"), None);
+        assert_eq!(child_path("textures", "textures2/ship.png"), None);
+        assert_eq!(child_path("", ""), None);
+    }
+}
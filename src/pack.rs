@@ -0,0 +1,285 @@
+use std::io;
+
+use crate::Compression;
+
+const MAGIC: &[u8; 8] = b"EMBASSET";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 16;
+const ENTRY_LEN: usize = 32;
+
+/// A set of assets packed into a single slice of bytes, as generated by the build script.
+///
+/// The pack starts with an index of the assets sorted by path, so looking up an asset is a
+/// binary search directly on the embedded bytes - nothing is copied or allocated when the pack is
+/// added to [`EmbassetIo`](crate::EmbassetIo).
+///
+/// Layout, with all numbers little endian and offsets relative to the start of the pack:
+///
+/// ```text
+/// header:  magic "EMBASSET", version: u32, count: u32
+/// index:   count * (path offset: u32, path length: u32, data offset: u64, data length: u64,
+///                   compression: u8, 7 bytes padding)
+/// paths:   UTF-8 paths, in index order
+/// data:    asset data, possibly compressed
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct AssetPack {
+    data: &'static [u8],
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PackEntry {
+    path: &'static str,
+    data: &'static [u8],
+    compression: u8,
+}
+
+impl AssetPack {
+    /// Use `data`, as generated by the build script, as a pack of assets.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `data` isn't a valid asset pack.
+    pub fn new(data: &'static [u8]) -> io::Result<Self> {
+        if data.len() < HEADER_LEN || &data[..8] != MAGIC {
+            return Err(invalid_data("not an asset pack"));
+        }
+        if read_u32(data, 8) != VERSION {
+            return Err(invalid_data("unsupported asset pack version"));
+        }
+        let pack = AssetPack {
+            data,
+            len: read_u32(data, 12) as usize,
+        };
+        if data.len() < HEADER_LEN + pack.len * ENTRY_LEN {
+            return Err(invalid_data("truncated asset pack index"));
+        }
+        let mut previous: Option<&str> = None;
+        for i in 0..pack.len {
+            let entry = pack
+                .read_entry(i)
+                .ok_or_else(|| invalid_data("invalid asset pack entry"))?;
+            if matches!(previous, Some(previous) if previous >= entry.path) {
+                return Err(invalid_data("asset pack index is not sorted"));
+            }
+            previous = Some(entry.path);
+        }
+        Ok(pack)
+    }
+
+    /// Number of assets in the pack.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Checks if the pack holds no assets.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Paths of all the assets in the pack, in sorted order.
+    pub fn paths(&self) -> impl Iterator<Item = &'static str> {
+        let pack = *self;
+        (0..self.len).map(move |i| pack.entry(i).path)
+    }
+
+    /// Get the (decompressed) data of the asset at `path`, which must be normalized.
+    ///
+    /// Returns `None` if the pack doesn't hold the asset.
+    pub(crate) fn load(&self, path: &str) -> Option<io::Result<Vec<u8>>> {
+        let entry = self.entry(self.find(path)?);
+        Some(
+            Compression::from_id(entry.compression)
+                .ok_or_else(|| invalid_data("unsupported compression"))
+                .and_then(|compression| compression.decompress(entry.data)),
+        )
+    }
+
+    /// Paths of the assets with a path starting with `prefix`, in sorted order.
+    pub(crate) fn paths_starting_with<'a>(
+        &self,
+        prefix: &'a str,
+    ) -> impl Iterator<Item = &'static str> + 'a {
+        let pack = *self;
+        let start = self.partition_point(|path| path < prefix);
+        (start..self.len)
+            .map(move |i| pack.entry(i).path)
+            .take_while(move |path| path.starts_with(prefix))
+    }
+
+    fn find(&self, path: &str) -> Option<usize> {
+        let i = self.partition_point(|candidate| candidate < path);
+        (i < self.len && self.entry(i).path == path).then_some(i)
+    }
+
+    /// Index of the first entry for which `pred` is false, given that the entries for which it
+    /// is true come first.
+    fn partition_point(&self, pred: impl Fn(&str) -> bool) -> usize {
+        let (mut low, mut high) = (0, self.len);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if pred(self.entry(mid).path) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        low
+    }
+
+    /// The entry at `index`, which has been validated by [`AssetPack::new`].
+    fn entry(&self, index: usize) -> PackEntry {
+        self.read_entry(index).expect("validated asset pack entry")
+    }
+
+    fn read_entry(&self, index: usize) -> Option<PackEntry> {
+        let offset = HEADER_LEN + index * ENTRY_LEN;
+        let path_start = read_u32(self.data, offset) as usize;
+        let path_len = read_u32(self.data, offset + 4) as usize;
+        let data_start = usize::try_from(read_u64(self.data, offset + 8)).ok()?;
+        let data_len = usize::try_from(read_u64(self.data, offset + 16)).ok()?;
+        let path = self
+            .data
+            .get(path_start..path_start.checked_add(path_len)?)?;
+        Some(PackEntry {
+            path: std::str::from_utf8(path).ok()?,
+            data: self
+                .data
+                .get(data_start..data_start.checked_add(data_len)?)?,
+            compression: self.data[offset + 24],
+        })
+    }
+}
+
+/// Packs the assets, given as `(path, compression, data)` sorted by path, into a single buffer.
+///
+/// Identical data is only stored once, shared by all of the assets holding it.
+#[cfg(feature = "build")]
+pub(crate) fn write_pack(assets: &[(&str, Compression, &[u8])]) -> Vec<u8> {
+    use std::collections::HashMap;
+
+    let paths_len: usize = assets.iter().map(|(path, _, _)| path.len()).sum();
+    let mut path_offset = HEADER_LEN + assets.len() * ENTRY_LEN;
+    let data_start = path_offset + paths_len;
+
+    let mut data_offsets: HashMap<&[u8], usize> = HashMap::new();
+    let mut blobs = vec![];
+    let mut data_len = 0;
+    for (_, _, data) in assets {
+        data_offsets.entry(data).or_insert_with(|| {
+            blobs.push(*data);
+            data_len += data.len();
+            data_start + data_len - data.len()
+        });
+    }
+
+    let mut pack = Vec::with_capacity(data_start + data_len);
+    pack.extend_from_slice(MAGIC);
+    pack.extend_from_slice(&VERSION.to_le_bytes());
+    pack.extend_from_slice(&(assets.len() as u32).to_le_bytes());
+    for (path, compression, data) in assets {
+        pack.extend_from_slice(&(path_offset as u32).to_le_bytes());
+        pack.extend_from_slice(&(path.len() as u32).to_le_bytes());
+        pack.extend_from_slice(&(data_offsets[data] as u64).to_le_bytes());
+        pack.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        pack.push(compression.id());
+        pack.extend_from_slice(&[0; 7]);
+        path_offset += path.len();
+    }
+    for (path, _, _) in assets {
+        pack.extend_from_slice(path.as_bytes());
+    }
+    for blob in blobs {
+        pack.extend_from_slice(blob);
+    }
+    pack
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(all(test, feature = "build"))]
+mod tests {
+    use super::{write_pack, AssetPack};
+    use crate::Compression;
+
+    fn pack(assets: &[(&str, Compression, &[u8])]) -> AssetPack {
+        AssetPack::new(Box::leak(write_pack(assets).into_boxed_slice())).unwrap()
+    }
+
+    #[test]
+    fn load_from_pack() {
+        let pack = pack(&[
+            ("asset.png", Compression::None, &[1, 2, 3]),
+            ("directory/asset.png", Compression::None, &[4, 5]),
+            ("directory/asset2.png", Compression::None, &[]),
+        ]);
+        assert_eq!(pack.len(), 3);
+        assert_eq!(pack.load("asset.png").unwrap().unwrap(), [1, 2, 3]);
+        assert_eq!(pack.load("directory/asset.png").unwrap().unwrap(), [4, 5]);
+        assert!(pack
+            .load("directory/asset2.png")
+            .unwrap()
+            .unwrap()
+            .is_empty());
+        assert!(pack.load("asset").is_none());
+        assert!(pack.load("directory").is_none());
+        assert!(pack.load("zzz.png").is_none());
+        assert_eq!(
+            pack.paths_starting_with("directory/").collect::<Vec<_>>(),
+            ["directory/asset.png", "directory/asset2.png"]
+        );
+        assert_eq!(pack.paths_starting_with("dir/").count(), 0);
+        assert_eq!(pack.paths().count(), 3);
+    }
+
+    #[cfg(feature = "deflate")]
+    #[test]
+    fn load_compressed_from_pack() {
+        let data = b"some highly compressible data data data data data".to_vec();
+        let compressed = Compression::Deflate.compress(&data).unwrap();
+        let pack = pack(&[("asset.txt", Compression::Deflate, &compressed)]);
+        assert_eq!(pack.load("asset.txt").unwrap().unwrap(), data);
+    }
+
+    #[test]
+    fn identical_data_is_stored_once() {
+        let data = [42; 1000];
+        let packed = write_pack(&[
+            ("a.png", Compression::None, &data),
+            ("b.png", Compression::None, &data),
+            ("c.png", Compression::None, &[1]),
+        ]);
+        assert!(packed.len() < 2 * data.len());
+        let pack = AssetPack::new(Box::leak(packed.into_boxed_slice())).unwrap();
+        assert_eq!(pack.load("a.png").unwrap().unwrap(), data);
+        assert_eq!(pack.load("b.png").unwrap().unwrap(), data);
+        assert_eq!(pack.load("c.png").unwrap().unwrap(), [1]);
+    }
+
+    #[test]
+    fn reject_invalid_packs() {
+        assert!(AssetPack::new(b"").is_err());
+        assert!(AssetPack::new(b"NOTAPACK\x01\0\0\0\0\0\0\0").is_err());
+        assert!(AssetPack::new(b"EMBASSET\x01\0\0\0\x01\0\0\0").is_err());
+        assert!(AssetPack::new(b"EMBASSET\x01\0\0\0\0\0\0\0")
+            .unwrap()
+            .is_empty());
+
+        let mut unsorted =
+            write_pack(&[("a", Compression::None, &[]), ("b", Compression::None, &[])]);
+        let paths = unsorted.len() - 2;
+        unsorted.swap(paths, paths + 1);
+        assert!(AssetPack::new(Box::leak(unsorted.into_boxed_slice())).is_err());
+    }
+}
@@ -1,15 +1,21 @@
 #[cfg(feature = "use-default-assetio")]
 use bevy::asset::create_platform_default_asset_io;
 use bevy::{
-    prelude::{App, AssetServer, Plugin},
+    prelude::{App, AssetServer, CoreStage, EventWriter, Local, Plugin, Res},
     tasks::IoTaskPool,
+    utils::{Duration, Instant},
 };
 
-use crate::EmbassetIo;
+use crate::{AssetChanged, EmbassetIo};
 
 /// Bevy plugin that will insert [`EmbassetIo`](EmbassetIo) instead of the default
 /// [`AssetServer`](bevy::asset::AssetServer) added by the [`AssetPlugin`](bevy::asset::AssetPlugin).
 ///
+/// The [`RuntimeAssets`](crate::RuntimeAssets) of the `EmbassetIo` are inserted as a resource, for
+/// adding, replacing and removing assets at runtime, and an [`AssetChanged`] event is sent when an
+/// asset loaded through a [`DirectoryAssetIo`](crate::DirectoryAssetIo) watching for changes is
+/// changed on disk.
+///
 /// # Examples
 ///
 /// If you are using the [`DefaultPlugins`](bevy::prelude::DefaultPlugins) group from Bevy, it can
@@ -62,7 +68,7 @@ use crate::EmbassetIo;
 /// ```ignore
 /// bevy_embasset::include_all_assets(
 ///  &Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("assets")
-/// );
+/// )?;
 /// ```
 ///
 /// and includeded in the source:
@@ -129,6 +135,30 @@ where
             .0
             .clone();
 
+        app.insert_resource(bevy_embasset.runtime_assets());
         app.insert_resource(AssetServer::new(bevy_embasset, task_pool));
+        app.add_event::<AssetChanged>()
+            .add_system_to_stage(CoreStage::PreUpdate, detect_changed_assets);
+    }
+}
+
+/// How often the watched assets are checked for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+fn detect_changed_assets(
+    asset_server: Res<AssetServer>,
+    mut last_check: Local<Option<Instant>>,
+    mut events: EventWriter<AssetChanged>,
+) {
+    if matches!(*last_check, Some(last) if last.elapsed() < WATCH_INTERVAL) {
+        return;
+    }
+    *last_check = Some(Instant::now());
+    if let Some(io) = asset_server.asset_io().downcast_ref::<EmbassetIo>() {
+        events.send_batch(
+            io.changed_paths()
+                .into_iter()
+                .map(|path| AssetChanged { path }),
+        );
     }
 }
@@ -0,0 +1,142 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use bevy::utils::HashMap;
+
+use crate::normalize::{child_path, normalize};
+
+/// Handle to the assets added to [`EmbassetIo`](crate::EmbassetIo) at runtime, e.g. generated
+/// or downloaded data.
+///
+/// The handle is cheap to clone, and all clones share the same assets. [`EmbassetPlugin`]
+/// inserts it as a resource, so systems can add, replace and remove assets after the
+/// `EmbassetIo` has been handed to the [`AssetServer`](bevy::asset::AssetServer):
+///
+/// ```ignore
+/// fn add_level(assets: Res<RuntimeAssets>) {
+///     assets.insert("levels/custom.ron", generate_level());
+/// }
+/// ```
+///
+/// Assets added at runtime form the layer [`RUNTIME_LAYER`](crate::RUNTIME_LAYER), taking
+/// precedence over all other layers of the `EmbassetIo`, and changes are visible to subsequent
+/// loads. As the `AssetServer` keeps loaded assets around, a replaced
+/// asset that is already loaded is only picked up once all of its handles have been dropped.
+///
+/// [`EmbassetPlugin`]: crate::EmbassetPlugin
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeAssets {
+    assets: Arc<RwLock<HashMap<String, Arc<[u8]>>>>,
+}
+
+impl RuntimeAssets {
+    /// Create an empty set of assets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the asset at `path`, replacing any asset previously added at the same path.
+    ///
+    /// The path is normalized, just like for
+    /// [`EmbassetIo::add_embedded_asset`](crate::EmbassetIo::add_embedded_asset). Returns the
+    /// data of the replaced asset, if any.
+    pub fn insert(&self, path: impl AsRef<Path>, data: impl Into<Arc<[u8]>>) -> Option<Arc<[u8]>> {
+        self.assets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(normalize(path.as_ref()), data.into())
+    }
+
+    /// Remove the asset at `path`, returning its data if it was added.
+    pub fn remove(&self, path: impl AsRef<Path>) -> Option<Arc<[u8]>> {
+        self.assets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&normalize(path.as_ref()))
+    }
+
+    /// Get the data of the asset at `path`, if added.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<Arc<[u8]>> {
+        self.load(&normalize(path.as_ref()))
+    }
+
+    /// Number of assets added.
+    pub fn len(&self) -> usize {
+        self.assets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Checks if no assets are added.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the data of the asset at `path`, which must be normalized.
+    pub(crate) fn load(&self, path: &str) -> Option<Arc<[u8]>> {
+        self.assets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(path)
+            .cloned()
+    }
+
+    /// Checks if `path`, which must be normalized, is a directory holding any of the assets.
+    pub(crate) fn is_directory(&self, path: &str) -> bool {
+        let assets = self.assets.read().unwrap_or_else(PoisonError::into_inner);
+        if path.is_empty() {
+            return !assets.is_empty();
+        }
+        let as_folder = format!("{}/", path);
+        assets.keys().any(|asset| asset.starts_with(&as_folder))
+    }
+
+    /// Paths of the assets and directories directly in the directory `path`, which must be
+    /// normalized, in sorted order.
+    ///
+    /// Returns `None` if the directory doesn't hold any of the assets.
+    pub(crate) fn read_directory(&self, path: &str) -> Option<Vec<PathBuf>> {
+        if !self.is_directory(path) {
+            return None;
+        }
+        let mut paths = self
+            .assets
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .keys()
+            .filter_map(|asset| child_path(path, asset))
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        paths.sort();
+        paths.dedup();
+        Some(paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::RuntimeAssets;
+
+    #[test]
+    fn shared_between_clones() {
+        let assets = RuntimeAssets::new();
+        let handle = assets.clone();
+        assert!(handle.insert("levels\\custom.ron", vec![1, 2]).is_none());
+        assert_eq!(assets.get("levels/custom.ron").unwrap().as_ref(), [1, 2]);
+
+        let data: Arc<[u8]> = Arc::from(&[3][..]);
+        assert_eq!(
+            handle.insert("levels/custom.ron", data).unwrap().as_ref(),
+            [1, 2]
+        );
+        assert_eq!(assets.get("levels/custom.ron").unwrap().as_ref(), [3]);
+        assert_eq!(assets.len(), 1);
+        assert!(assets.remove("./levels/custom.ron").is_some());
+        assert!(handle.is_empty());
+    }
+}
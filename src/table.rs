@@ -0,0 +1,107 @@
+use std::io;
+
+use crate::Compression;
+
+/// Assets in a perfect-hash lookup table, generated at compile time by the build script.
+///
+/// Besides the assets themselves, the table holds an index of the directories, listing the
+/// assets and subdirectories directly in each directory. Both looking up an asset and listing a
+/// directory is done without scanning the other assets, and adding the table to
+/// [`EmbassetIo`](crate::EmbassetIo) doesn't allocate.
+#[derive(Debug, Clone, Copy)]
+pub struct AssetTable {
+    assets: &'static phf::Map<&'static str, StaticAsset>,
+    directories: &'static phf::Map<&'static str, &'static [&'static str]>,
+}
+
+/// An asset in an [`AssetTable`].
+#[derive(Debug, Clone, Copy)]
+pub struct StaticAsset {
+    data: &'static [u8],
+    compression: Compression,
+}
+
+impl AssetTable {
+    /// Create a table from the maps generated by the build script.
+    ///
+    /// `assets` maps normalized asset paths to the assets, and `directories` maps each directory
+    /// (with the root being `""`) to the paths of the assets and subdirectories directly in it.
+    pub const fn new(
+        assets: &'static phf::Map<&'static str, StaticAsset>,
+        directories: &'static phf::Map<&'static str, &'static [&'static str]>,
+    ) -> Self {
+        AssetTable {
+            assets,
+            directories,
+        }
+    }
+
+    /// Number of assets in the table.
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// Checks if the table holds no assets.
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+
+    /// Get the (decompressed) data of the asset at `path`, which must be normalized.
+    ///
+    /// Returns `None` if the table doesn't hold the asset.
+    pub(crate) fn load(&self, path: &str) -> Option<io::Result<Vec<u8>>> {
+        self.assets
+            .get(path)
+            .map(|asset| asset.compression.decompress(asset.data))
+    }
+
+    /// Paths of the assets and subdirectories directly in the directory `path`, which must be
+    /// normalized.
+    ///
+    /// Returns `None` if the directory isn't in the table.
+    pub(crate) fn directory(&self, path: &str) -> Option<&'static [&'static str]> {
+        self.directories.get(path).copied()
+    }
+}
+
+impl StaticAsset {
+    /// An asset holding `data`, compressed using `compression`.
+    pub const fn new(data: &'static [u8], compression: Compression) -> Self {
+        StaticAsset { data, compression }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use phf::phf_map;
+
+    use super::{AssetTable, StaticAsset};
+    use crate::Compression;
+
+    static ASSETS: phf::Map<&'static str, StaticAsset> = phf_map! {
+        "asset.png" => StaticAsset::new(&[1, 2, 3], Compression::None),
+        "directory/asset.png" => StaticAsset::new(&[4, 5, 6], Compression::None),
+    };
+    static DIRECTORIES: phf::Map<&'static str, &'static [&'static str]> = phf_map! {
+        "" => &["asset.png", "directory"],
+        "directory" => &["directory/asset.png"],
+    };
+
+    #[test]
+    fn load_from_table() {
+        let table = AssetTable::new(&ASSETS, &DIRECTORIES);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.load("asset.png").unwrap().unwrap(), [1, 2, 3]);
+        assert_eq!(
+            table.load("directory/asset.png").unwrap().unwrap(),
+            [4, 5, 6]
+        );
+        assert!(table.load("directory").is_none());
+        assert_eq!(
+            table.directory("directory").unwrap(),
+            ["directory/asset.png"]
+        );
+        assert_eq!(table.directory("").unwrap().len(), 2);
+        assert!(table.directory("asset.png").is_none());
+    }
+}
@@ -0,0 +1,271 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use bevy::asset::{AssetIo, AssetIoError, BoxedFuture};
+use derive_more::DebugCustom;
+use flate2::read::GzDecoder;
+use tar::{Archive, EntryType};
+
+use crate::archive::ArchiveIndex;
+
+/// The first bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// [`AssetIo`] serving the assets in a tar archive, optionally gzipped.
+///
+/// The archive is indexed once, when the `TarAssetIo` is created. Paths are relative to the root
+/// of the archive, and only regular files and directories are served - links are ignored.
+///
+/// An embedded archive is served directly from the embedded bytes, and an uncompressed archive on
+/// disk is read from when loading. As a gzipped archive can't be read from at random, it is
+/// decompressed into memory when created.
+///
+/// ```ignore
+/// App::new().add_embasset_plugin(|io| {
+///     io.add_handler(AssetIoAlternative::new(
+///         "bundle://",
+///         TarAssetIo::from_bytes(include_bytes!("../bundle.tar.gz")).unwrap(),
+///         false,
+///     ));
+/// });
+/// ```
+///
+/// # Requires
+///
+/// Feature: `tar`
+#[derive(DebugCustom)]
+#[debug(fmt = "TarAssetIo {{ assets = {} }}", "index.len()")]
+pub struct TarAssetIo {
+    data: TarData,
+    /// Location of the content of each file in the archive.
+    index: ArchiveIndex<Range<usize>>,
+}
+
+#[derive(Debug)]
+enum TarData {
+    Embedded(&'static [u8]),
+    Decompressed(Vec<u8>),
+    File(Mutex<File>),
+}
+
+impl TarAssetIo {
+    /// Read the tar archive at `path`, which may be gzipped.
+    ///
+    /// An uncompressed archive is kept open, and assets are read from it when loaded.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be read, or isn't a valid archive.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut magic = [0; 2];
+        let gzipped = matches!(file.read_exact(&mut magic), Ok(()) if magic == GZIP_MAGIC);
+        file.rewind()?;
+        if gzipped {
+            Self::decompressed(file)
+        } else {
+            let index = index(&mut file)?;
+            Ok(TarAssetIo {
+                data: TarData::File(Mutex::new(file)),
+                index,
+            })
+        }
+    }
+
+    /// Use `data`, e.g. included with [`include_bytes!`], as a tar archive, which may be gzipped.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `data` isn't a valid archive.
+    pub fn from_bytes(data: &'static [u8]) -> io::Result<Self> {
+        if data.starts_with(&GZIP_MAGIC) {
+            Self::decompressed(data)
+        } else {
+            Ok(TarAssetIo {
+                index: index(data)?,
+                data: TarData::Embedded(data),
+            })
+        }
+    }
+
+    /// Number of assets in the archive.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Checks if the archive holds no assets.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Decompress the gzipped archive read from `reader` into memory.
+    fn decompressed(reader: impl Read) -> io::Result<Self> {
+        let mut data = vec![];
+        GzDecoder::new(reader).read_to_end(&mut data)?;
+        Ok(TarAssetIo {
+            index: index(data.as_slice())?,
+            data: TarData::Decompressed(data),
+        })
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<u8>, AssetIoError> {
+        let range = self
+            .index
+            .get(path)
+            .ok_or_else(|| AssetIoError::NotFound(path.to_path_buf()))?
+            .clone();
+        let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tar archive");
+        let bytes = match &self.data {
+            TarData::Embedded(bytes) => bytes,
+            TarData::Decompressed(bytes) => bytes.as_slice(),
+            TarData::File(file) => {
+                let mut file = file.lock().unwrap_or_else(PoisonError::into_inner);
+                file.seek(SeekFrom::Start(range.start as u64))?;
+                let mut data = vec![0; range.len()];
+                file.read_exact(&mut data)?;
+                return Ok(data);
+            }
+        };
+        Ok(bytes.get(range).ok_or_else(truncated)?.to_vec())
+    }
+}
+
+/// Index the files and directories in the uncompressed archive read from `reader`.
+fn index(reader: impl Read) -> io::Result<ArchiveIndex<Range<usize>>> {
+    let mut index = ArchiveIndex::new();
+    for entry in Archive::new(reader).entries()? {
+        let entry = entry?;
+        let path = entry.path()?.to_string_lossy().into_owned();
+        match entry.header().entry_type() {
+            EntryType::Regular | EntryType::Continuous => {
+                let start = usize::try_from(entry.raw_file_position())
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                let len = usize::try_from(entry.size())
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                index.insert_file(&path, start..start + len);
+            }
+            EntryType::Directory => index.insert_directory(&path),
+            _ => {}
+        }
+    }
+    Ok(index)
+}
+
+impl AssetIo for TarAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move { self.load(path) })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        match self.index.read_directory(path) {
+            Some(paths) => Ok(Box::new(paths.into_iter())),
+            None => Err(AssetIoError::NotFound(path.to_path_buf())),
+        }
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.index.is_directory(path)
+    }
+
+    fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        io::Write,
+        path::{Path, PathBuf},
+    };
+
+    use bevy::{asset::AssetIo, utils::futures::now_or_never};
+    use flate2::{write::GzEncoder, Compression};
+    use tar::{Builder, EntryType, Header};
+
+    use super::TarAssetIo;
+    use crate::test_util::test_dir;
+
+    fn archive() -> Vec<u8> {
+        let mut builder = Builder::new(vec![]);
+        let mut append = |path: &str, entry_type: EntryType, data: &[u8]| {
+            let mut header = Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, path, data).unwrap();
+        };
+        append("icon.png", EntryType::Regular, b"icon");
+        append("levels/", EntryType::Directory, b"");
+        append("levels/1.ron", EntryType::Regular, &[b'1'; 1000]);
+        append("./sounds/theme.ogg", EntryType::Regular, b"ogg");
+        append("empty/", EntryType::Directory, b"");
+        builder.into_inner().unwrap()
+    }
+
+    fn gzipped(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn check(io: &TarAssetIo) {
+        assert_eq!(io.len(), 3);
+        let load = |path: &str| now_or_never(io.load_path(Path::new(path))).unwrap();
+        assert_eq!(load("icon.png").unwrap(), b"icon");
+        assert_eq!(load("levels/1.ron").unwrap(), [b'1'; 1000]);
+        assert_eq!(load("sounds/theme.ogg").unwrap(), b"ogg");
+        assert!(load("levels").is_err());
+
+        assert!(io.is_directory(Path::new("sounds")));
+        assert!(io.is_directory(Path::new("empty")));
+        assert!(!io.is_directory(Path::new("icon.png")));
+        assert_eq!(
+            io.read_directory(Path::new(""))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            ["empty", "icon.png", "levels", "sounds"]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            io.read_directory(Path::new("levels"))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            [PathBuf::from("levels/1.ron")]
+        );
+        assert!(io.read_directory(Path::new("missing")).is_err());
+    }
+
+    #[test]
+    fn load_from_embedded_tar() {
+        let tar = Box::leak(archive().into_boxed_slice());
+        check(&TarAssetIo::from_bytes(tar).unwrap());
+        let tar_gz = Box::leak(gzipped(tar).into_boxed_slice());
+        check(&TarAssetIo::from_bytes(tar_gz).unwrap());
+    }
+
+    #[test]
+    fn load_from_tar_file() {
+        let dir = test_dir("tar-file");
+        fs::write(dir.join("assets.tar"), archive()).unwrap();
+        fs::write(dir.join("assets.tar.gz"), gzipped(&archive())).unwrap();
+        check(&TarAssetIo::open(dir.join("assets.tar")).unwrap());
+        check(&TarAssetIo::open(dir.join("assets.tar.gz")).unwrap());
+        assert!(TarAssetIo::open(dir.join("missing.tar")).is_err());
+    }
+}
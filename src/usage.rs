@@ -0,0 +1,68 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+};
+
+use bevy::prelude::warn;
+
+/// Records the paths successfully requested from [`EmbassetIo`](crate::EmbassetIo).
+///
+/// The file holds one normalized path per line, with directories ending in `/` (the root being
+/// just `/`). The paths already in the file are kept, so the usage of several sessions is
+/// merged, and the file is rewritten as soon as a new path is requested, so nothing is lost if
+/// the game doesn't exit cleanly.
+#[derive(Debug)]
+pub(crate) struct UsageRecorder {
+    file: PathBuf,
+    used: Mutex<BTreeSet<String>>,
+}
+
+impl UsageRecorder {
+    pub(crate) fn new(file: PathBuf) -> Self {
+        let used = match fs::read_to_string(&file) {
+            Ok(recorded) => recorded
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => BTreeSet::new(),
+        };
+        UsageRecorder {
+            file,
+            used: Mutex::new(used),
+        }
+    }
+
+    /// Records the asset at the normalized `path`.
+    pub(crate) fn asset(&self, path: &str) {
+        self.record(path.to_string());
+    }
+
+    /// Records the directory at the normalized `path`.
+    pub(crate) fn directory(&self, path: &str) {
+        self.record(format!("{}/", path));
+    }
+
+    fn record(&self, entry: String) {
+        let mut used = self.used.lock().unwrap_or_else(PoisonError::into_inner);
+        if used.insert(entry) {
+            if let Err(err) = self.write(&used) {
+                warn!(?err, file=?self.file, "failed writing asset usage");
+            }
+        }
+    }
+
+    fn write(&self, used: &BTreeSet<String>) -> io::Result<()> {
+        if let Some(parent) = self.file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut content = String::new();
+        for entry in used {
+            content.push_str(entry);
+            content.push('\n');
+        }
+        fs::write(&self.file, content)
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    fs::File,
+    io::{self, Cursor, Read, Seek},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use bevy::asset::{AssetIo, AssetIoError, BoxedFuture};
+use derive_more::DebugCustom;
+use zip::ZipArchive;
+
+use crate::archive::ArchiveIndex;
+
+/// Anything a zip archive can be read from.
+trait ZipSource: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ZipSource for T {}
+
+/// [`AssetIo`] serving the assets in a zip archive, e.g. a mod or DLC.
+///
+/// The index of the archive is read once, when the `ZipAssetIo` is created, and both stored and
+/// deflated entries are supported. Paths are relative to the root of the archive.
+///
+/// Use it for a prefix, or as a layer of [`EmbassetIo`](crate::EmbassetIo):
+///
+/// ```ignore
+/// App::new().add_embasset_plugin(|io| {
+///     io.add_handler(AssetIoAlternative::new(
+///         "dlc://",
+///         ZipAssetIo::open("dlc.zip").unwrap(),
+///         false,
+///     ))
+///     .add_layer("mod", 10, ZipAssetIo::open("mods/mod.zip").unwrap());
+/// });
+/// ```
+///
+/// # Requires
+///
+/// Feature: `zip`
+#[derive(DebugCustom)]
+#[debug(fmt = "ZipAssetIo {{ assets = {} }}", "index.len()")]
+pub struct ZipAssetIo {
+    archive: Mutex<ZipArchive<Box<dyn ZipSource>>>,
+    /// Index of the entry of each file in the archive.
+    index: ArchiveIndex<usize>,
+}
+
+impl ZipAssetIo {
+    /// Read the zip archive at `path`.
+    ///
+    /// The file is kept open, and assets are read from it when loaded.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file can't be opened, or isn't a valid zip archive.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+
+    /// Use `data`, e.g. included with [`include_bytes!`], as a zip archive.
+    ///
+    /// # Errors
+    ///
+    /// Fails if `data` isn't a valid zip archive.
+    pub fn from_bytes(data: &'static [u8]) -> io::Result<Self> {
+        Self::new(Cursor::new(data))
+    }
+
+    /// Read the zip archive from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if reading fails, or the data isn't a valid zip archive.
+    pub fn new(reader: impl Read + Seek + Send + 'static) -> io::Result<Self> {
+        let reader: Box<dyn ZipSource> = Box::new(reader);
+        let mut archive = ZipArchive::new(reader)?;
+        let mut index = ArchiveIndex::new();
+        for i in 0..archive.len() {
+            let entry = archive.by_index_raw(i)?;
+            if entry.is_dir() {
+                index.insert_directory(entry.name());
+            } else {
+                index.insert_file(entry.name(), i);
+            }
+        }
+        Ok(ZipAssetIo {
+            archive: Mutex::new(archive),
+            index,
+        })
+    }
+
+    /// Number of assets in the archive.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Checks if the archive holds no assets.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<u8>, AssetIoError> {
+        let i = *self
+            .index
+            .get(path)
+            .ok_or_else(|| AssetIoError::NotFound(path.to_path_buf()))?;
+        let mut archive = self.archive.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut entry = archive.by_index(i).map_err(io::Error::from)?;
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl AssetIo for ZipAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move { self.load(path) })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        match self.index.read_directory(path) {
+            Some(paths) => Ok(Box::new(paths.into_iter())),
+            None => Err(AssetIoError::NotFound(path.to_path_buf())),
+        }
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.index.is_directory(path)
+    }
+
+    fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Cursor, Write},
+        path::{Path, PathBuf},
+    };
+
+    use bevy::{asset::AssetIo, utils::futures::now_or_never};
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    use super::ZipAssetIo;
+
+    fn archive() -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("icon.png", stored).unwrap();
+        writer.write_all(b"icon").unwrap();
+        writer.add_directory("levels/", stored).unwrap();
+        writer.start_file("levels/1.ron", deflated).unwrap();
+        writer.write_all(&[b'1'; 1000]).unwrap();
+        writer.start_file("sounds/theme.ogg", deflated).unwrap();
+        writer.write_all(b"ogg").unwrap();
+        writer.add_directory("empty/", stored).unwrap();
+        writer.start_file("../outside.png", stored).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn load_from_zip() {
+        let io = ZipAssetIo::from_bytes(Box::leak(archive().into_boxed_slice())).unwrap();
+        assert_eq!(io.len(), 3);
+        let load = |path: &str| now_or_never(io.load_path(Path::new(path))).unwrap();
+        assert_eq!(load("icon.png").unwrap(), b"icon");
+        assert_eq!(load("./levels\\1.ron").unwrap(), [b'1'; 1000]);
+        assert_eq!(load("sounds/theme.ogg").unwrap(), b"ogg");
+        assert!(load("../outside.png").is_err());
+        assert!(load("levels").is_err());
+    }
+
+    #[test]
+    fn zip_directories() {
+        let io = ZipAssetIo::new(Cursor::new(archive())).unwrap();
+        assert!(io.is_directory(Path::new("")));
+        assert!(io.is_directory(Path::new("levels")));
+        assert!(io.is_directory(Path::new("sounds")));
+        assert!(io.is_directory(Path::new("empty")));
+        assert!(!io.is_directory(Path::new("icon.png")));
+        assert_eq!(
+            io.read_directory(Path::new(""))
+                .unwrap()
+                .collect::<Vec<_>>(),
+            ["empty", "icon.png", "levels", "sounds"]
+                .iter()
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(io.read_directory(Path::new("empty")).unwrap().count(), 0);
+        assert!(io.read_directory(Path::new("missing")).is_err());
+    }
+}